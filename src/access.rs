@@ -0,0 +1,580 @@
+//! Typed accessors for parsed values.
+//!
+//! The `as_…` methods on [`Taml`] and [`Taml::get_path`] / [`MapExt::get_path`]
+//! return [`Diagnostic`]s with the span of the offending value when its shape doesn't match,
+//! so they can be passed on to a [`Reporter`](`crate::diagnostics::Reporter`) directly.
+//!
+//! Paths are written like `a.b[2].c`, with keys quoted in backticks where necessary (`` `a b`.c ``).
+//! Keys look into maps and structured variants, and indices into lists and tuple variants.
+//!
+//! Integers and decimals are converted exactly or not at all, apart from [`Taml::as_f64`], which rounds.
+//! The `"num-bigint"` and `"rust_decimal"` features add conversions to arbitrary-precision integers and to [`Decimal`](`rust_decimal::Decimal`).
+//! `-0` converts to zero (or negative zero where the target type has one). [`Taml::is_negative_zero`] detects it.
+
+use crate::{
+	diagnostics::{Diagnostic, DiagnosticLabel, DiagnosticLabelPriority, DiagnosticType},
+	parsing::{Key, List, Map, Taml, TamlValue, VariantPayload},
+	path::{parse_path, PathPart},
+	DataLiteral, Position,
+};
+use std::{borrow::Cow, fmt::Display, ops::Range, str::FromStr};
+
+fn diagnostic<P>(
+	type_: DiagnosticType,
+	caption: impl Into<Cow<'static, str>>,
+	span: Option<Range<P>>,
+) -> Diagnostic<P> {
+	Diagnostic::new(
+		type_,
+		vec![DiagnosticLabel::new(
+			caption.into(),
+			span,
+			DiagnosticLabelPriority::Primary,
+		)],
+	)
+}
+
+fn invalid_path<P>(path: &str) -> Diagnostic<P> {
+	diagnostic(
+		DiagnosticType::InvalidValue,
+		format!("`{}` is not a valid path.", path),
+		None,
+	)
+}
+
+impl<'a, P: Position> Taml<'a, P> {
+	/// A short description of the kind of value, like "an integer".
+	fn kind(&self) -> &'static str {
+		match &self.value {
+			TamlValue::String(_) => "a string",
+			TamlValue::DataLiteral(_) => "a data literal",
+			TamlValue::Integer(_) => "an integer",
+			TamlValue::Decimal(_) => "a decimal",
+			TamlValue::List(_) => "a list",
+			TamlValue::Map(_) => "a map",
+			TamlValue::EnumVariant { .. } => "an enum variant",
+			TamlValue::Error => "an invalid value",
+		}
+	}
+
+	fn invalid_type(&self, expected: &str) -> Diagnostic<P> {
+		diagnostic(
+			DiagnosticType::InvalidType,
+			format!("Expected {}, found {}.", expected, self.kind()),
+			Some(self.span.clone()),
+		)
+	}
+
+	/// Interprets the unit variants `true` and `false` as [`bool`].
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not `true` or `false`.
+	pub fn as_bool(&self) -> Result<bool, Diagnostic<P>> {
+		match &self.value {
+			TamlValue::EnumVariant {
+				key,
+				payload: VariantPayload::Unit,
+			} if key.as_ref() == "true" => Ok(true),
+			TamlValue::EnumVariant {
+				key,
+				payload: VariantPayload::Unit,
+			} if key.as_ref() == "false" => Ok(false),
+			_ => Err(self.invalid_type("`true` or `false`")),
+		}
+	}
+
+	fn integer_str(&self) -> Result<&'a str, Diagnostic<P>> {
+		match &self.value {
+			TamlValue::Integer(str) => Ok(str),
+			_ => Err(self.invalid_type("an integer")),
+		}
+	}
+
+	fn out_of_range(&self, str: &str, type_name: &str, range: impl Display) -> Diagnostic<P> {
+		diagnostic(
+			DiagnosticType::InvalidValue,
+			format!(
+				"{} is out of range for `{}`, which holds {}.",
+				str, type_name, range
+			),
+			Some(self.span.clone()),
+		)
+	}
+
+	fn as_integer<T: FromStr + Display + Copy>(
+		&self,
+		type_name: &str,
+		min: T,
+		max: T,
+	) -> Result<T, Diagnostic<P>> {
+		let str = self.integer_str()?;
+		// Unsigned types don't parse `-0`.
+		let digits = if str == "-0" { "0" } else { str };
+		digits
+			.parse()
+			.map_err(|_| self.out_of_range(str, type_name, format_args!("{} to {}", min, max)))
+	}
+
+	/// Whether `self` is the integer `-0` or a decimal like `-0.0`,
+	/// which the grammar allows to be interpreted differently from `0`.
+	#[must_use]
+	pub fn is_negative_zero(&self) -> bool {
+		match &self.value {
+			TamlValue::Integer(str) | TamlValue::Decimal(str) => matches!(
+				str.strip_prefix('-'),
+				Some(digits) if digits.chars().all(|c| c == '0' || c == '.')
+			),
+			_ => false,
+		}
+	}
+
+	/// Parses an integer as [`i64`].
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not an integer or out of range.
+	pub fn as_i64(&self) -> Result<i64, Diagnostic<P>> {
+		self.as_integer("i64", i64::MIN, i64::MAX)
+	}
+
+	/// Parses an integer as [`u64`].
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not an integer or out of range.
+	pub fn as_u64(&self) -> Result<u64, Diagnostic<P>> {
+		self.as_integer("u64", u64::MIN, u64::MAX)
+	}
+
+	/// Parses an integer as [`i128`].
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not an integer or out of range.
+	pub fn as_i128(&self) -> Result<i128, Diagnostic<P>> {
+		self.as_integer("i128", i128::MIN, i128::MAX)
+	}
+
+	/// Parses an integer as [`u128`].
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not an integer or out of range.
+	pub fn as_u128(&self) -> Result<u128, Diagnostic<P>> {
+		self.as_integer("u128", u128::MIN, u128::MAX)
+	}
+
+	/// Parses an integer as [`BigInt`](`num_bigint::BigInt`).
+	///
+	/// Requires the `"num-bigint"` feature.
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not an integer.
+	#[cfg(feature = "num-bigint")]
+	pub fn as_big_int(&self) -> Result<num_bigint::BigInt, Diagnostic<P>> {
+		let str = self.integer_str()?;
+		str.parse()
+			.map_err(|_| self.out_of_range(str, "BigInt", "any integer"))
+	}
+
+	/// Parses an integer as [`BigUint`](`num_bigint::BigUint`).
+	///
+	/// Requires the `"num-bigint"` feature.
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not an integer or negative.
+	#[cfg(feature = "num-bigint")]
+	pub fn as_big_uint(&self) -> Result<num_bigint::BigUint, Diagnostic<P>> {
+		let str = self.integer_str()?;
+		let digits = if str == "-0" { "0" } else { str };
+		digits
+			.parse()
+			.map_err(|_| self.out_of_range(str, "BigUint", "0 and up"))
+	}
+
+	/// Converts a decimal or integer into a [`Decimal`](`rust_decimal::Decimal`) exactly, keeping its sign.
+	///
+	/// The scale is that of the literal with trailing zeros trimmed, so `1.50` has the scale 1.
+	///
+	/// Requires the `"rust_decimal"` feature.
+	///
+	/// # Errors
+	///
+	/// Iff `self` is neither a decimal nor an integer, or can't be represented without rounding,
+	/// i.e. has more than 28 fractional digits or a mantissa of more than 96 bits.
+	#[cfg(feature = "rust_decimal")]
+	pub fn as_decimal(&self) -> Result<rust_decimal::Decimal, Diagnostic<P>> {
+		use std::convert::TryFrom as _;
+
+		let str = match &self.value {
+			TamlValue::Integer(str) | TamlValue::Decimal(str) => str,
+			_ => return Err(self.invalid_type("a decimal")),
+		};
+		let (whole, fraction) = str.split_once('.').unwrap_or((str, ""));
+		let mut decimal = format!("{}{}", whole, fraction)
+			.parse()
+			.ok()
+			.and_then(|mantissa: i128| {
+				rust_decimal::Decimal::try_from_i128_with_scale(
+					mantissa,
+					u32::try_from(fraction.len()).ok()?,
+				)
+				.ok()
+			})
+			.ok_or_else(|| {
+				diagnostic(
+					DiagnosticType::InvalidValue,
+					format!(
+						"{} can't be represented exactly as `Decimal`, \
+						which holds at most 28 fractional digits and a 96-bit mantissa.",
+						str
+					),
+					Some(self.span.clone()),
+				)
+			})?;
+		// Keeps the sign of `-0`.
+		decimal.set_sign_negative(str.starts_with('-'));
+		Ok(decimal)
+	}
+
+	/// Parses a decimal or integer as [`f64`], rounding to the nearest representable value.
+	///
+	/// # Errors
+	///
+	/// Iff `self` is neither a decimal nor an integer.
+	pub fn as_f64(&self) -> Result<f64, Diagnostic<P>> {
+		match &self.value {
+			// Always succeeds, as TAML integers and decimals are valid `f64` literals.
+			TamlValue::Integer(str) | TamlValue::Decimal(str) => {
+				str.parse().map_err(|_| self.invalid_type("a decimal"))
+			}
+			_ => Err(self.invalid_type("a decimal")),
+		}
+	}
+
+	/// Retrieves the text of a string.
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not a string.
+	pub fn as_str(&self) -> Result<&str, Diagnostic<P>> {
+		match &self.value {
+			TamlValue::String(string) => Ok(string.as_ref()),
+			_ => Err(self.invalid_type("a string")),
+		}
+	}
+
+	/// Retrieves a data literal, which can be decoded with [`Encodings`](`crate::encoding::Encodings`).
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not a data literal.
+	pub fn as_data_literal(&self) -> Result<&DataLiteral<'a, P>, Diagnostic<P>> {
+		match &self.value {
+			TamlValue::DataLiteral(data_literal) => Ok(data_literal),
+			_ => Err(self.invalid_type("a data literal")),
+		}
+	}
+
+	/// Retrieves the entries of a map.
+	///
+	/// Structured variants are not maps. Use [`Taml::as_variant`] for them instead.
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not a map.
+	pub fn as_map(&self) -> Result<&Map<'a, P>, Diagnostic<P>> {
+		match &self.value {
+			TamlValue::Map(map) => Ok(map),
+			_ => Err(self.invalid_type("a map")),
+		}
+	}
+
+	/// Retrieves the items of a list.
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not a list.
+	pub fn as_list(&self) -> Result<&List<'a, P>, Diagnostic<P>> {
+		match &self.value {
+			TamlValue::List(list) => Ok(list),
+			_ => Err(self.invalid_type("a list")),
+		}
+	}
+
+	/// Retrieves the identifier and payload of an enum variant.
+	///
+	/// # Errors
+	///
+	/// Iff `self` is not an enum variant.
+	pub fn as_variant(&self) -> Result<(&Key<'a, P>, &VariantPayload<'a, P>), Diagnostic<P>> {
+		match &self.value {
+			TamlValue::EnumVariant { key, payload } => Ok((key, payload)),
+			_ => Err(self.invalid_type("an enum variant")),
+		}
+	}
+
+	/// Looks up the value at `path` (like `a.b[2].c`) below `self`.
+	///
+	/// # Errors
+	///
+	/// Iff `path` is malformed ([`DiagnosticType::InvalidValue`] without span),
+	/// a value along it has the wrong shape ([`DiagnosticType::InvalidType`]),
+	/// a key is missing ([`DiagnosticType::MissingField`]) or an index is out of range ([`DiagnosticType::InvalidLength`]).
+	pub fn get_path(&self, path: &str) -> Result<&Self, Diagnostic<P>> {
+		parse_path(path)
+			.map_err(|()| invalid_path(path))?
+			.iter()
+			.try_fold(self, |taml, part| taml.get_part(part))
+	}
+
+	fn get_part(&self, part: &PathPart) -> Result<&Self, Diagnostic<P>> {
+		match part {
+			PathPart::Key(key) => match &self.value {
+				TamlValue::Map(map)
+				| TamlValue::EnumVariant {
+					payload: VariantPayload::Structured(map),
+					..
+				} => get_key(map, key, Some(&self.span)),
+				_ => Err(self.invalid_type("a map or structured variant")),
+			},
+			PathPart::Index(index) => match &self.value {
+				TamlValue::List(list)
+				| TamlValue::EnumVariant {
+					payload: VariantPayload::Tuple(list),
+					..
+				} => list.get(*index).ok_or_else(|| {
+					diagnostic(
+						DiagnosticType::InvalidLength,
+						format!(
+							"Index {} is out of range for a list with {} items.",
+							index,
+							list.len()
+						),
+						Some(self.span.clone()),
+					)
+				}),
+				_ => Err(self.invalid_type("a list or tuple variant")),
+			},
+		}
+	}
+}
+
+fn get_key<'t, 'a, P: Position>(
+	map: &'t Map<'a, P>,
+	key: &str,
+	span: Option<&Range<P>>,
+) -> Result<&'t Taml<'a, P>, Diagnostic<P>> {
+	map.get(key).ok_or_else(|| {
+		diagnostic(
+			DiagnosticType::MissingField,
+			format!("Missing field `{}`.", key),
+			span.cloned(),
+		)
+	})
+}
+
+/// Path lookup on [`Map`]s, which are a type alias and so can't have inherent methods.
+pub trait MapExt<'a, P: Position> {
+	/// Looks up the value at `path` (like `a.b[2].c`) in this map, like [`Taml::get_path`].
+	///
+	/// Errors about the map itself have no span.
+	///
+	/// # Errors
+	///
+	/// See [`Taml::get_path`].
+	fn get_path(&self, path: &str) -> Result<&Taml<'a, P>, Diagnostic<P>>;
+}
+
+impl<'a, P: Position> MapExt<'a, P> for Map<'a, P> {
+	fn get_path(&self, path: &str) -> Result<&Taml<'a, P>, Diagnostic<P>> {
+		let parts = parse_path(path).map_err(|()| invalid_path(path))?;
+		match parts.split_first() {
+			Some((PathPart::Key(first), rest)) => rest
+				.iter()
+				.try_fold(get_key(self, first, None)?, |taml, part| {
+					taml.get_part(part)
+				}),
+			_ => Err(diagnostic(
+				DiagnosticType::InvalidType,
+				"Expected a list, found a map.",
+				None,
+			)),
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn numbers() {
+	use crate::Token;
+	use logos::Logos as _;
+
+	let text = "zero: -0
+decimal_zero: -0.0
+positive: 0
+large: 340282366920938463463374607431768211456
+small: -9223372036854775809
+decimal: -1.50
+precise: 0.12345678901234567890123456789
+";
+	let map = crate::parse(Token::lexer(text).spanned(), &mut ()).unwrap();
+	let get = |path: &str| map.get_path(path).unwrap();
+
+	assert!(get("zero").is_negative_zero());
+	assert!(get("decimal_zero").is_negative_zero());
+	assert!(!get("positive").is_negative_zero());
+	assert!(!get("decimal").is_negative_zero());
+	assert_eq!(get("zero").as_u64(), Ok(0));
+	assert_eq!(get("zero").as_i64(), Ok(0));
+	assert!(get("zero").as_f64().unwrap().is_sign_negative());
+
+	let error = get("large").as_u128().unwrap_err();
+	assert_eq!(error.type_, DiagnosticType::InvalidValue);
+	assert_eq!(
+		error.labels[0].caption.as_deref(),
+		Some(
+			"340282366920938463463374607431768211456 is out of range for `u128`, \
+			which holds 0 to 340282366920938463463374607431768211455."
+		)
+	);
+	assert_eq!(get("small").as_i128(), Ok(i128::from(i64::MIN) - 1));
+	assert_eq!(
+		get("small").as_i64().unwrap_err().labels[0].span,
+		Some(get("small").span.clone())
+	);
+
+	#[cfg(feature = "num-bigint")]
+	{
+		use num_bigint::{BigInt, BigUint};
+
+		assert_eq!(
+			get("large").as_big_uint(),
+			Ok(BigUint::from(u128::MAX) + 1_u8)
+		);
+		assert_eq!(get("small").as_big_int(), Ok(BigInt::from(i64::MIN) - 1));
+		assert_eq!(get("zero").as_big_uint(), Ok(BigUint::from(0_u8)));
+		assert_eq!(
+			get("small").as_big_uint().unwrap_err().type_,
+			DiagnosticType::InvalidValue
+		);
+		assert_eq!(
+			get("decimal").as_big_int().unwrap_err().type_,
+			DiagnosticType::InvalidType
+		);
+	}
+
+	#[cfg(feature = "rust_decimal")]
+	{
+		use rust_decimal::Decimal;
+
+		assert_eq!(get("decimal").as_decimal(), Ok(Decimal::new(-15, 1)));
+		assert_eq!(get("decimal").as_decimal().unwrap().scale(), 1);
+		assert_eq!(get("positive").as_decimal(), Ok(Decimal::ZERO));
+		assert!(get("decimal_zero").as_decimal().unwrap().is_sign_negative());
+		assert_eq!(
+			get("precise").as_decimal().unwrap_err().type_,
+			DiagnosticType::InvalidValue
+		);
+		assert_eq!(
+			get("large").as_decimal().unwrap_err().type_,
+			DiagnosticType::InvalidValue
+		);
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn accessors() {
+	use crate::Token;
+	use logos::Logos as _;
+
+	fn error<T: std::fmt::Debug>(
+		result: Result<T, Diagnostic<usize>>,
+	) -> (DiagnosticType, Option<Range<usize>>) {
+		let diagnostic = result.unwrap_err();
+		(diagnostic.type_, diagnostic.labels[0].span.clone())
+	}
+
+	let text = "flag: true
+small: -1
+large: 340282366920938463463374607431768211455
+decimal: 0.5
+string: \"text\"
+data: <hex:00>
+list: (1, Some(2))
+
+# `a b`.c
+d: 1
+
+# variant:Structured
+e: (())
+";
+	let map = crate::parse(Token::lexer(text).spanned(), &mut ()).unwrap();
+	let get = |path: &str| map.get_path(path).unwrap();
+
+	assert_eq!(get("flag").as_bool(), Ok(true));
+	assert_eq!(get("small").as_i64(), Ok(-1));
+	assert_eq!(get("small").as_f64(), Ok(-1.0));
+	assert_eq!(get("large").as_u128(), Ok(u128::MAX));
+	assert_eq!(get("decimal").as_f64(), Ok(0.5));
+	assert_eq!(get("string").as_str(), Ok("text"));
+	assert_eq!(&*get("data").as_data_literal().unwrap().encoding, "hex");
+	assert_eq!(get("list").as_list().map(Vec::len), Ok(2));
+	assert_eq!(get("list[0]").as_u64(), Ok(1));
+	assert_eq!(get("list[1][0]").as_u64(), Ok(2));
+	assert_eq!(get("`a b`.c").as_map().map(Map::len), Ok(1));
+	assert_eq!(get("`a b`.c.d").as_i128(), Ok(1));
+	assert_eq!(
+		get("variant").as_variant().map(|(key, _)| key.as_ref()),
+		Ok("Structured")
+	);
+	assert_eq!(get("variant.e[0]").as_list().map(Vec::len), Ok(0));
+
+	assert_eq!(
+		error(get("small").as_u64()),
+		(DiagnosticType::InvalidValue, Some(18..20))
+	);
+	assert_eq!(
+		error(get("large").as_i128()),
+		(DiagnosticType::InvalidValue, Some(28..67))
+	);
+	assert_eq!(
+		error(get("string").as_bool()),
+		(DiagnosticType::InvalidType, Some(89..95))
+	);
+	assert_eq!(
+		error(get("flag").as_str()),
+		(DiagnosticType::InvalidType, Some(6..10))
+	);
+	assert_eq!(
+		error(map.get_path("flag.a")),
+		(DiagnosticType::InvalidType, Some(6..10))
+	);
+	assert_eq!(
+		error(map.get_path("list[2]")),
+		(DiagnosticType::InvalidLength, Some(117..129))
+	);
+	assert_eq!(
+		error(map.get_path("`a b`.x")),
+		(
+			DiagnosticType::MissingField,
+			Some(get("`a b`").span.clone())
+		)
+	);
+	assert_eq!(
+		error(map.get_path("x")),
+		(DiagnosticType::MissingField, None)
+	);
+	assert_eq!(
+		error(map.get_path("[0]")),
+		(DiagnosticType::InvalidType, None)
+	);
+	assert_eq!(
+		error(map.get_path("a..b")),
+		(DiagnosticType::InvalidValue, None)
+	);
+}
@@ -0,0 +1,331 @@
+//! A lossless concrete syntax tree, which preserves comments, whitespace and layout.
+//!
+//! TAML is line-based, so the tree is too:
+//! A [`Cst`] is a sequence of [`Section`]s, each of which starts with a heading (except the first)
+//! and contains [`Entry`]s that each represent one key-value pair or table row.
+//! Blank lines and comment-only lines are attached to the entry they precede.
+//!
+//! [`Display`] reproduces the original text exactly,
+//! and [`Cst::parse`] produces the same [`Map`] as [`parse`](`crate::parse`) would from the same source.
+
+use crate::{diagnostics::Reporter, parsing::Map, Token};
+use logos::Logos as _;
+use std::{
+	fmt::{self, Display, Formatter},
+	iter,
+	ops::Range,
+};
+
+/// A lossless syntax tree of a TAML document.
+#[derive(Debug, Clone)]
+pub struct Cst<'a> {
+	/// The source text this tree was created from.
+	pub source: &'a str,
+	/// The sections of the document.
+	///
+	/// The first section is the implicit top-level one and never has a heading.
+	pub sections: Vec<Section<'a>>,
+	/// Blank and comment-only lines after the last entry or heading.
+	pub trailing_lines: Vec<Line<'a>>,
+	/// Whitespace after the last token.
+	pub trailing_whitespace: &'a str,
+}
+
+/// A heading and the entries up to the next heading.
+#[derive(Debug, Clone)]
+pub struct Section<'a> {
+	/// The heading that starts this section, or [`None`] for the top-level section.
+	pub heading: Option<Entry<'a>>,
+	/// The key-value pairs or table rows in this section.
+	pub entries: Vec<Entry<'a>>,
+}
+
+/// One meaningful line, together with the blank and comment-only lines before it.
+#[derive(Debug, Clone)]
+pub struct Entry<'a> {
+	/// Blank and comment-only lines between the previous entry (or heading) and this one.
+	pub leading_lines: Vec<Line<'a>>,
+	/// The line itself.
+	pub line: Line<'a>,
+	pub kind: EntryKind,
+}
+
+/// What an [`Entry`] represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+	Heading,
+	KeyValuePair,
+	Row,
+}
+
+/// A single line of the document.
+#[derive(Debug, Clone, Default)]
+pub struct Line<'a> {
+	/// Content tokens, excluding any postfix comment and the line break.
+	pub elements: Vec<Element<'a>>,
+	/// The comment at the end of this line, if any.
+	pub comment: Option<Element<'a>>,
+	/// The line break, which is missing only on the last line.
+	pub newline: Option<Element<'a>>,
+}
+
+/// A token with its exact source text and the whitespace before it.
+#[derive(Debug, Clone)]
+pub struct Element<'a> {
+	/// Whitespace between the previous token and this one.
+	pub leading_whitespace: &'a str,
+	/// The verbatim source text of [`token`](`Element::token`).
+	pub text: &'a str,
+	pub token: Token<'a, usize>,
+	pub span: Range<usize>,
+}
+
+impl<'a> Cst<'a> {
+	/// Creates a syntax tree from `source`.
+	///
+	/// This never fails: Unrecognised input is kept as [`Token::Error`] elements.
+	#[must_use]
+	pub fn new(source: &'a str) -> Self {
+		let mut lines = vec![];
+		let mut line = Line::default();
+		let mut end = 0;
+		let mut tokens = Token::lexer(source).spanned().peekable();
+		while let Some((token, span)) = tokens.next() {
+			let element = Element {
+				leading_whitespace: &source[end..span.start],
+				text: &source[span.clone()],
+				token,
+				span,
+			};
+			end = element.span.end;
+			match element.token {
+				Token::Newline => {
+					line.newline = Some(element);
+					lines.push(line);
+					line = Line::default();
+				}
+				// A comment followed by more tokens (after a verbatim carriage return) stays in order instead.
+				Token::Comment(_) if matches!(tokens.peek(), None | Some((Token::Newline, _))) => {
+					line.comment = Some(element);
+				}
+				_ => line.elements.push(element),
+			}
+		}
+		if !line.is_blank() || line.comment.is_some() {
+			lines.push(line);
+		}
+
+		let mut sections = vec![];
+		let mut section = Section {
+			heading: None,
+			entries: vec![],
+		};
+		let mut leading_lines = vec![];
+		for line in lines {
+			if line.is_blank() {
+				leading_lines.push(line);
+				continue;
+			}
+
+			let entry = Entry {
+				leading_lines: leading_lines.split_off(0),
+				kind: if line.is_heading() {
+					EntryKind::Heading
+				} else if section.is_tabular() {
+					EntryKind::Row
+				} else {
+					EntryKind::KeyValuePair
+				},
+				line,
+			};
+			if entry.kind == EntryKind::Heading {
+				sections.push(section);
+				section = Section {
+					heading: Some(entry),
+					entries: vec![],
+				};
+			} else {
+				section.entries.push(entry)
+			}
+		}
+		sections.push(section);
+
+		Self {
+			source,
+			sections,
+			trailing_lines: leading_lines,
+			trailing_whitespace: &source[end..],
+		}
+	}
+
+	/// Iterates over all lines in document order.
+	pub fn lines(&self) -> impl Iterator<Item = &Line<'a>> {
+		self.sections
+			.iter()
+			.flat_map(|section| section.heading.iter().chain(&section.entries))
+			.flat_map(|entry| entry.leading_lines.iter().chain(iter::once(&entry.line)))
+			.chain(&self.trailing_lines)
+	}
+
+	/// Iterates over all tokens in document order, including comments and line breaks.
+	pub fn elements(&self) -> impl Iterator<Item = &Element<'a>> {
+		self.lines().flat_map(Line::all_elements)
+	}
+
+	/// Parses this tree into a map, as [`parse`](`crate::parse`) would parse its source.
+	///
+	/// # Errors
+	///
+	/// Iff the source is not valid TAML.
+	pub fn parse(&self, reporter: &mut impl Reporter<usize>) -> Result<Map<'a, usize>, ()> {
+		crate::parse(
+			self.elements()
+				.map(|element| (element.token.clone(), element.span.clone())),
+			reporter,
+		)
+	}
+}
+
+impl Display for Cst<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		for element in self.elements() {
+			write!(f, "{}{}", element.leading_whitespace, element.text)?;
+		}
+		f.write_str(self.trailing_whitespace)
+	}
+}
+
+impl Section<'_> {
+	/// The number of hashes in this section's heading, or `0` for the top-level section.
+	#[must_use]
+	pub fn depth(&self) -> usize {
+		match self
+			.heading
+			.as_ref()
+			.map(|heading| &heading.line.elements[0].token)
+		{
+			Some(Token::HeadingHashes(depth)) => *depth,
+			_ => 0,
+		}
+	}
+
+	/// Whether this is a tabular section (i.e. its heading contains `[[`).
+	#[must_use]
+	pub fn is_tabular(&self) -> bool {
+		self.heading.as_ref().map_or(false, |heading| {
+			heading
+				.line
+				.elements
+				.windows(2)
+				.any(|pair| pair[0].token == Token::Brac && pair[1].token == Token::Brac)
+		})
+	}
+}
+
+impl<'a> Entry<'a> {
+	/// The comment-only lines directly above this entry, without blank lines in-between.
+	pub fn attached_comments(&self) -> impl Iterator<Item = &Element<'a>> {
+		let blank = self
+			.leading_lines
+			.iter()
+			.rposition(|line| line.comment.is_none());
+		self.leading_lines[blank.map_or(0, |blank| blank + 1)..]
+			.iter()
+			.filter_map(|line| line.comment.as_ref())
+	}
+
+	/// The byte range of this entry's line, excluding leading lines and its line break.
+	///
+	/// # Panics
+	///
+	/// Iff the line is blank, which is never the case for entries created by [`Cst::new`].
+	#[must_use]
+	pub fn span(&self) -> Range<usize> {
+		self.line.span().expect("Entries are never blank.")
+	}
+}
+
+impl<'a> Line<'a> {
+	/// Whether this line contains no tokens except (optionally) a comment and line break.
+	#[must_use]
+	pub fn is_blank(&self) -> bool {
+		self.elements.is_empty()
+	}
+
+	#[must_use]
+	pub fn is_heading(&self) -> bool {
+		matches!(
+			self.elements.first().map(|element| &element.token),
+			Some(Token::HeadingHashes(_))
+		)
+	}
+
+	/// Iterates over all tokens in this line, including the comment and line break.
+	pub fn all_elements(&self) -> impl Iterator<Item = &Element<'a>> {
+		self.elements
+			.iter()
+			.chain(&self.comment)
+			.chain(&self.newline)
+	}
+
+	/// The byte range from the first token to the end of the comment, if any, excluding the line break.
+	#[must_use]
+	pub fn span(&self) -> Option<Range<usize>> {
+		let first = self.elements.first().or(self.comment.as_ref())?;
+		let last = self.comment.as_ref().or_else(|| self.elements.last())?;
+		Some(first.span.start..last.span.end)
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn lossless() {
+	use crate::writing;
+
+	let source = "// Document comment.\r\n\n# [soundscapes]  \n\t// Sewer\n\n## [[loops].{sound, volume}]\n\"$sewer/amb_drips\",0.8\n\"$sewer/amb_flies\", 0.1000 // postfix comment\n\n// attached\n## moments\nsound :\"$sewer/moments/*\"\n  ";
+	let cst = Cst::new(source);
+	assert_eq!(cst.to_string(), source);
+
+	assert_eq!(cst.sections.len(), 4);
+	assert_eq!(cst.sections[3].depth(), 2);
+	assert!(cst.sections[2].is_tabular());
+	assert_eq!(cst.sections[2].entries[1].kind, EntryKind::Row);
+	assert_eq!(
+		cst.sections[2].entries[1]
+			.line
+			.comment
+			.as_ref()
+			.unwrap()
+			.text,
+		"// postfix comment"
+	);
+	assert_eq!(
+		cst.sections[3]
+			.heading
+			.as_ref()
+			.unwrap()
+			.attached_comments()
+			.map(|comment| comment.text)
+			.collect::<Vec<_>>(),
+		["// attached"]
+	);
+	assert_eq!(cst.sections[0].entries.len(), 0);
+	assert_eq!(cst.trailing_whitespace, "  ");
+
+	let map = cst.parse(&mut ()).unwrap();
+	assert_eq!(
+		writing::to_string(&map).unwrap(),
+		"# [soundscapes]\n\n## [[loops].{sound, volume}]\n\"$sewer/amb_drips\", 0.8\n\"$sewer/amb_flies\", 0.1\n\n## moments\nsound: \"$sewer/moments/*\"\n"
+	);
+
+	let carriage_return = "// c\r x: 1\n";
+	let cst = Cst::new(carriage_return);
+	assert_eq!(cst.to_string(), carriage_return);
+	assert!(cst
+		.elements()
+		.map(|element| element.span.clone())
+		.eq(Token::lexer(carriage_return)
+			.spanned()
+			.map(|(_, span)| span)));
+	assert!(cst.sections[0].entries[0].line.comment.is_none());
+}
@@ -0,0 +1,818 @@
+//! Deserialises parsed TAML into any [`Deserialize`] type.
+//!
+//! Requires the `"serde"` feature.
+//!
+//! Enum variants map to Serde enums by their payload: Unit variants (`Variant`) to unit variants,
+//! variants with a single list item (`Variant(1)`) to newtype variants, other lists to tuple variants
+//! and structured variants (`# key:Variant`) to struct variants.
+//! `true` and `false` are accepted as booleans.
+//!
+//! Data literals are decoded with the [`Encodings`] passed to the `…_with` functions, or the built-in ones otherwise.
+//! They can be deserialised as byte buffers, sequences of bytes (like `Vec<u8>` and `[u8; 16]`) and, if valid UTF-8, strings.
+//!
+//! Errors are reported as [`Diagnostic`]s through a [`Reporter`], with the span of the offending value.
+
+use crate::{
+	diagnostics::{
+		Diagnostic, DiagnosticLabel, DiagnosticLabelPriority, DiagnosticLevel, DiagnosticType,
+		Reporter,
+	},
+	encoding::Encodings,
+	parsing::{parse, Key, List, ListIter, Map, MapIter, Taml, TamlValue, VariantPayload},
+	Position, Token,
+};
+use cervine::Cow;
+use logos::Logos as _;
+use serde::{
+	de::{self, value::SeqDeserializer, DeserializeSeed, Expected, Unexpected, Visitor},
+	forward_to_deserialize_any, Deserialize,
+};
+use std::{
+	fmt::{self, Display, Formatter},
+	ops::Range,
+};
+
+/// Parses `text` and deserialises it into a `T`, with the built-in [`Encodings`].
+///
+/// Any errors are reported via `reporter`.
+///
+/// # Errors
+///
+/// Iff `text` is not valid TAML, including when any error-level diagnostic is reported while parsing,
+/// or can't be deserialised into a `T`.
+pub fn from_str<'de, T: Deserialize<'de>>(
+	text: &'de str,
+	reporter: &mut impl Reporter<usize>,
+) -> Result<T, ()> {
+	from_str_with(text, &Encodings::default(), reporter)
+}
+
+/// Parses `text` and deserialises it into a `T`, decoding data literals with `encodings`.
+///
+/// Any errors are reported via `reporter`.
+///
+/// # Errors
+///
+/// Iff `text` is not valid TAML, including when any error-level diagnostic is reported while parsing,
+/// or can't be deserialised into a `T`.
+pub fn from_str_with<'de, T: Deserialize<'de>>(
+	text: &'de str,
+	encodings: &Encodings,
+	reporter: &mut impl Reporter<usize>,
+) -> Result<T, ()> {
+	let mut diagnostics = vec![];
+	let map = parse(Token::lexer(text).spanned(), &mut diagnostics);
+	let has_errors = diagnostics
+		.iter()
+		.any(|diagnostic| diagnostic.level() == DiagnosticLevel::Error);
+	reporter.report_many_with(|| diagnostics);
+	let map = map?;
+	if has_errors {
+		return Err(());
+	}
+	from_map_with(&map, encodings, reporter)
+}
+
+/// Deserialises the top-level `map` of a TAML document into a `T`, with the built-in [`Encodings`].
+///
+/// Any errors are reported via `reporter`. Errors that concern the whole map have no span.
+///
+/// # Errors
+///
+/// Iff `map` can't be deserialised into a `T`.
+pub fn from_map<'de, T: Deserialize<'de>, P: Position>(
+	map: &Map<'de, P>,
+	reporter: &mut impl Reporter<P>,
+) -> Result<T, ()> {
+	from_map_with(map, &Encodings::default(), reporter)
+}
+
+/// Deserialises the top-level `map` of a TAML document into a `T`, decoding data literals with `encodings`.
+///
+/// Any errors are reported via `reporter`. Errors that concern the whole map have no span.
+///
+/// # Errors
+///
+/// Iff `map` can't be deserialised into a `T`.
+pub fn from_map_with<'de, T: Deserialize<'de>, P: Position>(
+	map: &Map<'de, P>,
+	encodings: &Encodings,
+	reporter: &mut impl Reporter<P>,
+) -> Result<T, ()> {
+	T::deserialize(MapDeserializer {
+		map,
+		encodings,
+		reporter,
+	})
+	.map_err(|error| {
+		report(reporter, None, error);
+	})
+}
+
+/// Deserialises a single TAML value into a `T`, with the built-in [`Encodings`].
+///
+/// Any errors are reported via `reporter`.
+///
+/// # Errors
+///
+/// Iff `taml` can't be deserialised into a `T`.
+pub fn from_taml<'de, T: Deserialize<'de>, P: Position>(
+	taml: &Taml<'de, P>,
+	reporter: &mut impl Reporter<P>,
+) -> Result<T, ()> {
+	from_taml_with(taml, &Encodings::default(), reporter)
+}
+
+/// Deserialises a single TAML value into a `T`, decoding data literals with `encodings`.
+///
+/// Any errors are reported via `reporter`.
+///
+/// # Errors
+///
+/// Iff `taml` can't be deserialised into a `T`.
+pub fn from_taml_with<'de, T: Deserialize<'de>, P: Position>(
+	taml: &Taml<'de, P>,
+	encodings: &Encodings,
+	reporter: &mut impl Reporter<P>,
+) -> Result<T, ()> {
+	T::deserialize(Deserializer {
+		taml,
+		encodings,
+		reporter,
+	})
+	.map_err(|error| {
+		report(reporter, Some(&taml.span), error);
+	})
+}
+
+/// An error during deserialisation.
+///
+/// Errors are reported as [`Diagnostic`] as soon as the span they refer to is known,
+/// after which only [`Error::Reported`] is passed on.
+#[derive(Debug)]
+enum Error {
+	Unreported(DiagnosticType, std::string::String),
+	Reported,
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::Unreported(type_, message) => write!(f, "{}: {}", type_, message),
+			Error::Reported => write!(f, "Reported error"),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+	fn custom<T: Display>(msg: T) -> Self {
+		Error::Unreported(DiagnosticType::CustomErrorFromVisitor, msg.to_string())
+	}
+
+	fn invalid_type(unexp: Unexpected, exp: &dyn Expected) -> Self {
+		Error::Unreported(
+			DiagnosticType::InvalidType,
+			format!("Expected {}, found {}.", exp, unexp),
+		)
+	}
+
+	fn invalid_value(unexp: Unexpected, exp: &dyn Expected) -> Self {
+		Error::Unreported(
+			DiagnosticType::InvalidValue,
+			format!("Expected {}, found {}.", exp, unexp),
+		)
+	}
+
+	fn invalid_length(len: usize, exp: &dyn Expected) -> Self {
+		Error::Unreported(
+			DiagnosticType::InvalidLength,
+			format!("Expected {}, found {} elements.", exp, len),
+		)
+	}
+
+	fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+		Error::Unreported(
+			DiagnosticType::UnknownVariant,
+			format!("Unknown variant `{}`.{}", variant, one_of(expected)),
+		)
+	}
+
+	fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+		Error::Unreported(
+			DiagnosticType::UnknownField,
+			format!("Unknown field `{}`.{}", field, one_of(expected)),
+		)
+	}
+
+	fn missing_field(field: &'static str) -> Self {
+		Error::Unreported(
+			DiagnosticType::MissingField,
+			format!("Missing field `{}`.", field),
+		)
+	}
+}
+
+fn one_of(expected: &[&str]) -> std::string::String {
+	match expected {
+		[] => " There are none.".to_string(),
+		[single] => format!(" Expected `{}`.", single),
+		_ => format!(" Expected one of `{}`.", expected.join("`, `")),
+	}
+}
+
+/// Reports `error` at `span` unless it was already reported.
+fn report<P: Position>(
+	reporter: &mut impl Reporter<P>,
+	span: Option<&Range<P>>,
+	error: Error,
+) -> Error {
+	if let Error::Unreported(type_, message) = error {
+		reporter.report_with(|| {
+			Diagnostic::new(
+				type_,
+				vec![DiagnosticLabel::new(
+					message,
+					span.cloned(),
+					DiagnosticLabelPriority::Primary,
+				)],
+			)
+		});
+	}
+	Error::Reported
+}
+
+fn visit_integer<'de, V: Visitor<'de>>(str: &str, visitor: V) -> Result<V::Value, Error> {
+	if let Ok(i) = str.parse::<i64>() {
+		visitor.visit_i64(i)
+	} else if let Ok(u) = str.parse::<u64>() {
+		visitor.visit_u64(u)
+	} else if let Ok(i) = str.parse::<i128>() {
+		visitor.visit_i128(i)
+	} else if let Ok(u) = str.parse::<u128>() {
+		visitor.visit_u128(u)
+	} else {
+		Err(Error::Unreported(
+			DiagnosticType::InvalidValue,
+			format!("The integer {} is out of range.", str),
+		))
+	}
+}
+
+fn visit_list<'de, V: Visitor<'de>, P: Position>(
+	list: &List<'de, P>,
+	encodings: &Encodings,
+	reporter: &mut impl Reporter<P>,
+	visitor: V,
+) -> Result<V::Value, Error> {
+	let mut seq = SeqAccess {
+		iter: list.iter(),
+		encodings,
+		reporter,
+	};
+	let value = visitor.visit_seq(&mut seq)?;
+	match seq.iter.len() {
+		0 => Ok(value),
+		remaining => Err(Error::Unreported(
+			DiagnosticType::InvalidLength,
+			format!(
+				"Expected {} elements, found {}.",
+				list.len() - remaining,
+				list.len()
+			),
+		)),
+	}
+}
+
+fn unexpected_payload<P>(payload: &VariantPayload<P>) -> Unexpected<'static> {
+	match payload {
+		VariantPayload::Structured(_) => Unexpected::StructVariant,
+		VariantPayload::Tuple(list) if list.len() == 1 => Unexpected::NewtypeVariant,
+		VariantPayload::Tuple(_) => Unexpected::TupleVariant,
+		VariantPayload::Unit => Unexpected::UnitVariant,
+	}
+}
+
+struct Deserializer<'a, 'de, P, R> {
+	taml: &'a Taml<'de, P>,
+	encodings: &'a Encodings,
+	reporter: &'a mut R,
+}
+
+impl<'de, P: Position, R: Reporter<P>> Deserializer<'_, 'de, P, R> {
+	/// Deserialises data literals as sequence of bytes and everything else like [`de::Deserializer::deserialize_any`].
+	fn deserialize_bytes_as_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		let data_literal = match &self.taml.value {
+			TamlValue::DataLiteral(data_literal) => data_literal,
+			_ => return de::Deserializer::deserialize_any(self, visitor),
+		};
+		let bytes = self
+			.encodings
+			.decode(data_literal, self.reporter)
+			.map_err(|()| Error::Reported)?;
+		let mut seq = SeqDeserializer::new(bytes.into_iter());
+		visitor
+			.visit_seq(&mut seq)
+			.and_then(|value| seq.end().map(|()| value))
+			.map_err(|error| report(self.reporter, Some(&self.taml.span), error))
+	}
+}
+
+impl<'de, P: Position, R: Reporter<P>> de::Deserializer<'de> for Deserializer<'_, 'de, P, R> {
+	type Error = Error;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		let Self {
+			taml,
+			encodings,
+			reporter,
+		} = self;
+		let result = match &taml.value {
+			TamlValue::String(Cow::Borrowed(str)) => visitor.visit_borrowed_str(str),
+			TamlValue::String(Cow::Owned(string)) => visitor.visit_str(string),
+			TamlValue::DataLiteral(data_literal) => {
+				match encodings.decode(data_literal, reporter) {
+					Ok(bytes) => visitor.visit_byte_buf(bytes),
+					Err(()) => return Err(Error::Reported),
+				}
+			}
+			TamlValue::Integer(str) => visit_integer(str, visitor),
+			TamlValue::Decimal(str) => {
+				visitor.visit_f64(str.parse().expect("Decimals are always valid `f64`s."))
+			}
+			TamlValue::List(list) => visit_list(list, encodings, reporter, visitor),
+			TamlValue::Map(map) => visitor.visit_map(MapAccess {
+				iter: map.iter(),
+				value: None,
+				encodings,
+				reporter,
+			}),
+			TamlValue::EnumVariant { key, payload } => visitor.visit_enum(EnumAccess {
+				key,
+				payload,
+				encodings,
+				reporter,
+			}),
+			TamlValue::Error => Err(de::Error::invalid_type(
+				Unexpected::Other("invalid value"),
+				&visitor,
+			)),
+		};
+		result.map_err(|error| report(reporter, Some(&taml.span), error))
+	}
+
+	fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		let value = match &self.taml.value {
+			TamlValue::EnumVariant {
+				key,
+				payload: VariantPayload::Unit,
+			} => match key.as_ref() {
+				"true" => true,
+				"false" => false,
+				_ => return self.deserialize_any(visitor),
+			},
+			_ => return self.deserialize_any(visitor),
+		};
+		visitor
+			.visit_bool(value)
+			.map_err(|error| report(self.reporter, Some(&self.taml.span), error))
+	}
+
+	fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		// Absent values are represented by missing fields instead.
+		visitor.visit_some(self)
+	}
+
+	fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		match &self.taml.value {
+			TamlValue::List(list) if list.is_empty() => visitor
+				.visit_unit()
+				.map_err(|error| report(self.reporter, Some(&self.taml.span), error)),
+			_ => self.deserialize_any(visitor),
+		}
+	}
+
+	fn deserialize_unit_struct<V: Visitor<'de>>(
+		self,
+		_name: &'static str,
+		visitor: V,
+	) -> Result<V::Value, Error> {
+		self.deserialize_unit(visitor)
+	}
+
+	fn deserialize_newtype_struct<V: Visitor<'de>>(
+		self,
+		_name: &'static str,
+		visitor: V,
+	) -> Result<V::Value, Error> {
+		visitor.visit_newtype_struct(self)
+	}
+
+	fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		self.deserialize_bytes_as_seq(visitor)
+	}
+
+	fn deserialize_tuple<V: Visitor<'de>>(
+		self,
+		_len: usize,
+		visitor: V,
+	) -> Result<V::Value, Error> {
+		self.deserialize_bytes_as_seq(visitor)
+	}
+
+	fn deserialize_tuple_struct<V: Visitor<'de>>(
+		self,
+		_name: &'static str,
+		_len: usize,
+		visitor: V,
+	) -> Result<V::Value, Error> {
+		self.deserialize_bytes_as_seq(visitor)
+	}
+
+	fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		visitor.visit_unit()
+	}
+
+	forward_to_deserialize_any! {
+		i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf map struct enum identifier
+	}
+}
+
+/// Deserialises the top-level [`Map`], which has no span.
+struct MapDeserializer<'a, 'de, P, R> {
+	map: &'a Map<'de, P>,
+	encodings: &'a Encodings,
+	reporter: &'a mut R,
+}
+
+impl<'de, P: Position, R: Reporter<P>> de::Deserializer<'de> for MapDeserializer<'_, 'de, P, R> {
+	type Error = Error;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		visitor.visit_map(MapAccess {
+			iter: self.map.iter(),
+			value: None,
+			encodings: self.encodings,
+			reporter: self.reporter,
+		})
+	}
+
+	fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		visitor.visit_some(self)
+	}
+
+	fn deserialize_newtype_struct<V: Visitor<'de>>(
+		self,
+		_name: &'static str,
+		visitor: V,
+	) -> Result<V::Value, Error> {
+		visitor.visit_newtype_struct(self)
+	}
+
+	fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		visitor.visit_unit()
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf unit unit_struct seq tuple tuple_struct map struct enum identifier
+	}
+}
+
+/// Deserialises keys and variant identifiers.
+struct KeyDeserializer<'a, 'de, P, R> {
+	key: &'a Key<'de, P>,
+	reporter: &'a mut R,
+}
+
+impl<'de, P: Position, R: Reporter<P>> de::Deserializer<'de> for KeyDeserializer<'_, 'de, P, R> {
+	type Error = Error;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		match &self.key.name {
+			Cow::Borrowed(str) => visitor.visit_borrowed_str(str),
+			Cow::Owned(string) => visitor.visit_str(string),
+		}
+		.map_err(|error| report(self.reporter, Some(&self.key.span), error))
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf option unit unit_struct newtype_struct seq tuple
+		tuple_struct map struct enum identifier ignored_any
+	}
+}
+
+struct SeqAccess<'a, 'de, P, R> {
+	iter: ListIter<'a, 'de, P>,
+	encodings: &'a Encodings,
+	reporter: &'a mut R,
+}
+
+impl<'de, P: Position, R: Reporter<P>> de::SeqAccess<'de> for SeqAccess<'_, 'de, P, R> {
+	type Error = Error;
+
+	fn next_element_seed<T: DeserializeSeed<'de>>(
+		&mut self,
+		seed: T,
+	) -> Result<Option<T::Value>, Error> {
+		self.iter
+			.next()
+			.map(|taml| {
+				seed.deserialize(Deserializer {
+					taml,
+					encodings: self.encodings,
+					reporter: &mut *self.reporter,
+				})
+			})
+			.transpose()
+	}
+
+	fn size_hint(&self) -> Option<usize> {
+		Some(self.iter.len())
+	}
+}
+
+struct MapAccess<'a, 'de, P, R> {
+	iter: MapIter<'a, 'de, P>,
+	value: Option<&'a Taml<'de, P>>,
+	encodings: &'a Encodings,
+	reporter: &'a mut R,
+}
+
+impl<'de, P: Position, R: Reporter<P>> de::MapAccess<'de> for MapAccess<'_, 'de, P, R> {
+	type Error = Error;
+
+	fn next_key_seed<K: DeserializeSeed<'de>>(
+		&mut self,
+		seed: K,
+	) -> Result<Option<K::Value>, Error> {
+		let (key, value) = match self.iter.next() {
+			Some(entry) => entry,
+			None => return Ok(None),
+		};
+		self.value = Some(value);
+		seed.deserialize(KeyDeserializer {
+			key,
+			reporter: &mut *self.reporter,
+		})
+		.map(Some)
+	}
+
+	fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+		seed.deserialize(Deserializer {
+			taml: self
+				.value
+				.take()
+				.expect("`next_value_seed` called before `next_key_seed`."),
+			encodings: self.encodings,
+			reporter: &mut *self.reporter,
+		})
+	}
+
+	fn size_hint(&self) -> Option<usize> {
+		Some(self.iter.len())
+	}
+}
+
+struct EnumAccess<'a, 'de, P, R> {
+	key: &'a Key<'de, P>,
+	payload: &'a VariantPayload<'de, P>,
+	encodings: &'a Encodings,
+	reporter: &'a mut R,
+}
+
+impl<'de, P: Position, R: Reporter<P>> de::EnumAccess<'de> for EnumAccess<'_, 'de, P, R> {
+	type Error = Error;
+	type Variant = Self;
+
+	fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+		let value = seed.deserialize(KeyDeserializer {
+			key: self.key,
+			reporter: &mut *self.reporter,
+		})?;
+		Ok((value, self))
+	}
+}
+
+impl<'de, P: Position, R: Reporter<P>> de::VariantAccess<'de> for EnumAccess<'_, 'de, P, R> {
+	type Error = Error;
+
+	fn unit_variant(self) -> Result<(), Error> {
+		match self.payload {
+			VariantPayload::Unit => Ok(()),
+			payload => Err(de::Error::invalid_type(
+				unexpected_payload(payload),
+				&"unit variant",
+			)),
+		}
+	}
+
+	fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+		match self.payload {
+			VariantPayload::Tuple(list) if list.len() == 1 => seed.deserialize(Deserializer {
+				taml: &list[0],
+				encodings: self.encodings,
+				reporter: self.reporter,
+			}),
+			payload => Err(de::Error::invalid_type(
+				unexpected_payload(payload),
+				&"newtype variant",
+			)),
+		}
+	}
+
+	fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+		match self.payload {
+			VariantPayload::Tuple(list) => visit_list(list, self.encodings, self.reporter, visitor),
+			payload => Err(de::Error::invalid_type(
+				unexpected_payload(payload),
+				&"tuple variant",
+			)),
+		}
+	}
+
+	fn struct_variant<V: Visitor<'de>>(
+		self,
+		_fields: &'static [&'static str],
+		visitor: V,
+	) -> Result<V::Value, Error> {
+		match self.payload {
+			VariantPayload::Structured(map) => visitor.visit_map(MapAccess {
+				iter: map.iter(),
+				value: None,
+				encodings: self.encodings,
+				reporter: self.reporter,
+			}),
+			payload => Err(de::Error::invalid_type(
+				unexpected_payload(payload),
+				&"struct variant",
+			)),
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn deserialize() {
+	use serde::Deserialize;
+
+	#[derive(Debug, Deserialize, PartialEq)]
+	enum Variant {
+		Unit,
+		Newtype(u8),
+		Tuple(u8, std::string::String),
+		Struct { a: i128 },
+	}
+
+	#[derive(Debug, Deserialize, PartialEq)]
+	struct Inner<'a> {
+		borrowed: &'a str,
+		escaped: std::string::String,
+		bytes: Vec<u8>,
+		uuid: [u8; 16],
+		text: std::string::String,
+	}
+
+	#[derive(Debug, Deserialize, PartialEq)]
+	struct Config<'a> {
+		integer: i8,
+		large: u128,
+		decimal: f64,
+		flag: bool,
+		optional: Option<u8>,
+		absent: Option<u8>,
+		unit: (),
+		list: Vec<u8>,
+		variants: Vec<Variant>,
+		structured: Variant,
+		#[serde(borrow)]
+		inner: Inner<'a>,
+	}
+
+	let text = r#"integer: -5
+large: 340282366920938463463374607431768211455
+decimal: 0.5
+flag: true
+optional: 1
+unit: ()
+list: (1, 2, 3)
+variants: (Unit, Newtype(1), Tuple(2, "two"))
+
+# structured:Struct
+a: -1
+
+# inner
+borrowed: "text"
+escaped: "\"quoted\""
+bytes: <hex:81 F0>
+uuid: <uuid:67e55044-10b1-426f-9247-bb680e5fe0c8>
+text: <utf8:\>>
+"#;
+	let mut diagnostics = vec![];
+	let config: Config = from_str(text, &mut diagnostics).unwrap();
+	assert_eq!(diagnostics, []);
+	assert_eq!(
+		config,
+		Config {
+			integer: -5,
+			large: u128::MAX,
+			decimal: 0.5,
+			flag: true,
+			optional: Some(1),
+			absent: None,
+			unit: (),
+			list: vec![1, 2, 3],
+			variants: vec![
+				Variant::Unit,
+				Variant::Newtype(1),
+				Variant::Tuple(2, "two".to_string())
+			],
+			structured: Variant::Struct { a: -1 },
+			inner: Inner {
+				borrowed: "text",
+				escaped: "\"quoted\"".to_string(),
+				bytes: vec![0x81, 0xF0],
+				uuid: [
+					0x67, 0xE5, 0x50, 0x44, 0x10, 0xB1, 0x42, 0x6F, 0x92, 0x47, 0xBB, 0x68, 0x0E,
+					0x5F, 0xE0, 0xC8,
+				],
+				text: ">".to_string(),
+			},
+		}
+	);
+}
+
+#[cfg(test)]
+#[test]
+fn errors() {
+	use serde::Deserialize;
+
+	#[derive(Debug, Deserialize)]
+	#[serde(deny_unknown_fields)]
+	#[allow(dead_code)]
+	struct Config {
+		a: u8,
+		b: (u8, u8),
+		c: Vec<bool>,
+	}
+
+	let text = "a: 256\nb: (1, 2, 3)\nc: (true, maybe)\nd: 1\n";
+	let mut diagnostics = vec![];
+	from_str::<Config>(text, &mut diagnostics).unwrap_err();
+	assert_eq!(
+		diagnostics
+			.iter()
+			.map(|diagnostic| (diagnostic.type_, diagnostic.labels[0].span.clone()))
+			.collect::<Vec<_>>(),
+		[(DiagnosticType::InvalidValue, Some(3..6))]
+	);
+
+	for (text, type_, span) in [
+		(
+			"a: 1\nb: (1, 2, 3)\n",
+			DiagnosticType::InvalidLength,
+			Some(8..17),
+		),
+		(
+			"a: 1\nb: (1, 2)\nc: (true, maybe)\n",
+			DiagnosticType::InvalidType,
+			Some(25..30),
+		),
+		(
+			"a: 1\nb: (1, 2)\nc: ()\nd: 1\n",
+			DiagnosticType::UnknownField,
+			Some(21..22),
+		),
+		("a: 1\nc: ()\n", DiagnosticType::MissingField, None),
+		("a: <hex:0>\n", DiagnosticType::EncodeFailed, Some(8..9)),
+		("a: <hex:00>\n", DiagnosticType::InvalidType, Some(3..11)),
+		(
+			"a: <base99:00>\n",
+			DiagnosticType::UnknownEncoding,
+			Some(4..10),
+		),
+	] {
+		let mut diagnostics = vec![];
+		from_str::<Config>(text, &mut diagnostics).unwrap_err();
+		assert_eq!(
+			diagnostics
+				.iter()
+				.map(|diagnostic| (diagnostic.type_, diagnostic.labels[0].span.clone()))
+				.collect::<Vec<_>>(),
+			[(type_, span)],
+			"{}",
+			text
+		);
+	}
+
+	// The parser recovers from the misplaced comment, but reports it as error.
+	let mut diagnostics = vec![];
+	from_str::<std::collections::BTreeMap<String, i64>>("a: 1 # b\n", &mut diagnostics)
+		.unwrap_err();
+	assert!(diagnostics
+		.iter()
+		.any(|diagnostic| diagnostic.level() == DiagnosticLevel::Error));
+}
@@ -1,10 +1,21 @@
 use enum_properties::enum_properties;
+
+pub mod explanations;
+pub mod json;
+pub mod rendering;
+pub mod sarif;
+
 use std::{
 	borrow::Cow, convert::Into, fmt::Display, iter, ops::Range, string::String as stdString,
 };
 
-#[derive(Clone, Copy, Debug)]
+/// How severe a [`Diagnostic`] is, ordered from least to most severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum DiagnosticLevel {
+	/// The diagnostic is suppressed and shouldn't be shown.
+	Allow,
+	Help,
+	Note,
 	Warning,
 	Error,
 }
@@ -200,6 +211,20 @@ enum_properties! {
 			title: "Misplaced data",
 		},
 
+		NonListValueSelected {
+			group: DiagnosticGroup::Parsing,
+			code: 20,
+			level: DiagnosticLevel::Error,
+			title: "Non-list value selected",
+		},
+
+		TupleVariantValueNotAList {
+			group: DiagnosticGroup::Parsing,
+			code: 21,
+			level: DiagnosticLevel::Error,
+			title: "Tuple variant value is not a list",
+		},
+
 		CustomErrorFromVisitor {
 			group: DiagnosticGroup::Deserialising,
 			code: 0,
@@ -265,6 +290,80 @@ enum_properties! {
 	}
 }
 
+impl DiagnosticType {
+	/// All [`DiagnosticType`]s, in declaration order.
+	pub const ALL: &'static [Self] = &[
+		Self::UnrecognizedToken,
+		Self::ZeroPrefixedDecimalFound,
+		Self::ZeroPrefixedIntegerFound,
+		Self::VerbatimCarriageReturnInsideLiteral,
+		Self::HeadingTooDeep,
+		Self::SubsectionInTabularSection,
+		Self::MissingVariantIdentifier,
+		Self::ExpectedKeyValuePair,
+		Self::ExpectedValue,
+		Self::UnclosedList,
+		Self::ValuesLineTooShort,
+		Self::ExpectedListIdentifier,
+		Self::ExpectedTabularPathSegment,
+		Self::UnclosedTabularPathMultiSegment,
+		Self::ExpectedPathSegment,
+		Self::InvalidPathContinuation,
+		Self::KeyPreviouslyDefined,
+		Self::UnclosedListKey,
+		Self::UnclosedTabularPathSection,
+		Self::DuplicateEnumInstantiation,
+		Self::NonMapValueSelected,
+		Self::MisplacedComment,
+		Self::MisplacedHeading,
+		Self::MisplacedData,
+		Self::NonListValueSelected,
+		Self::TupleVariantValueNotAList,
+		Self::CustomErrorFromVisitor,
+		Self::InvalidType,
+		Self::InvalidValue,
+		Self::InvalidLength,
+		Self::UnknownVariant,
+		Self::UnknownField,
+		Self::MissingField,
+		Self::UnknownEncoding,
+		Self::EncodeFailed,
+	];
+
+	/// Looks up a [`DiagnosticType`] by its code, with or without `TAML-` prefix (e.g. `"TAML-P0013"` or `"P0013"`).
+	///
+	/// TAML-P0015 is shared by [`UnclosedTabularPathSection`](`DiagnosticType::UnclosedTabularPathSection`)
+	/// and [`DuplicateEnumInstantiation`](`DiagnosticType::DuplicateEnumInstantiation`) and finds the former.
+	#[must_use]
+	pub fn from_code(code: &str) -> Option<Self> {
+		let (group, number) = parse_code(code)?;
+		Self::ALL
+			.iter()
+			.copied()
+			.find(|type_| type_.group.code == group && type_.code == number)
+	}
+
+	/// This [`DiagnosticType`]'s code with `TAML-` prefix, e.g. `"TAML-P0013"`.
+	#[must_use]
+	pub fn code(&self) -> stdString {
+		format!("TAML-{}{:04}", self.group.code, self.code)
+	}
+
+	/// This [`DiagnosticType`]'s group code and number, as returned by [`parse_code`].
+	pub(crate) fn code_parts(&self) -> (char, usize) {
+		(self.group.code, self.code)
+	}
+}
+
+/// Splits a code, with or without `TAML-` prefix, into its group code and number.
+pub(crate) fn parse_code(code: &str) -> Option<(char, usize)> {
+	let code = code.strip_prefix("TAML-").unwrap_or(code);
+	let mut chars = code.chars();
+	let group = chars.next()?;
+	let number = chars.as_str().parse::<usize>().ok()?;
+	Some((group, number))
+}
+
 impl Display for DiagnosticType {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		write!(f, "TAML-{}{:03} {}", self.group.code, self.code, self.title)
@@ -302,21 +401,73 @@ impl<Position> DiagnosticLabel<Position> {
 	}
 }
 
+/// How confident the parser is that a [`Suggestion`] is what the user intended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applicability {
+	/// The suggestion is definitely correct and can be applied automatically, e.g. by [`apply_fixes`].
+	MachineApplicable,
+	/// The suggestion is likely correct, but should be reviewed before it is applied.
+	MaybeIncorrect,
+	/// The replacement contains placeholders that must be filled in by the user.
+	HasPlaceholders,
+}
+
+/// A fix-it for a [`Diagnostic`]: Replace the source at `span` with `replacement`.
+///
+/// Insertions have an empty `span`, deletions an empty `replacement`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion<Position> {
+	pub message: Cow<'static, str>,
+	pub span: Range<Position>,
+	pub replacement: Cow<'static, str>,
+	pub applicability: Applicability,
+}
+
+impl<Position> Suggestion<Position> {
+	pub fn new(
+		message: impl Into<Cow<'static, str>>,
+		span: Range<Position>,
+		replacement: impl Into<Cow<'static, str>>,
+		applicability: Applicability,
+	) -> Self {
+		Self {
+			message: message.into(),
+			span,
+			replacement: replacement.into(),
+			applicability,
+		}
+	}
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Diagnostic<Position> {
 	pub type_: DiagnosticType,
 	pub labels: Vec<DiagnosticLabel<Position>>,
+	pub suggestions: Vec<Suggestion<Position>>,
+	/// Replaces the [`DiagnosticType`]'s default level if [`Some`], e.g. as configured through a [`SeverityMap`].
+	pub level_override: Option<DiagnosticLevel>,
 }
 
 impl<Position> Diagnostic<Position> {
+	/// Creates a [`Diagnostic`] without suggestions, at the [`DiagnosticType`]'s default level.
+	#[must_use]
+	pub fn new(type_: DiagnosticType, labels: Vec<DiagnosticLabel<Position>>) -> Self {
+		Self {
+			type_,
+			labels,
+			suggestions: vec![],
+			level_override: None,
+		}
+	}
+
 	#[must_use]
 	pub fn code(&self) -> stdString {
-		format!("TAML-{}{:04}", self.type_.group.code, self.type_.code)
+		self.type_.code()
 	}
 
 	#[must_use]
 	pub fn level(&self) -> DiagnosticLevel {
-		self.type_.level
+		self.level_override.unwrap_or(self.type_.level)
 	}
 
 	#[must_use]
@@ -325,6 +476,38 @@ impl<Position> Diagnostic<Position> {
 	}
 }
 
+/// Applies all [`MachineApplicable`](`Applicability::MachineApplicable`) suggestions in `diagnostics` to `source`.
+///
+/// Suggestions are applied in order of their spans. A suggestion that overlaps one that was already applied is skipped,
+/// so the result may still contain some errors. Parsing it again and repeating this process will fix those too where possible.
+///
+/// # Panics
+///
+/// Iff a suggestion's span is out of bounds or not on [`char`] boundaries in `source`.
+#[must_use]
+pub fn apply_fixes(source: &str, diagnostics: &[Diagnostic<usize>]) -> stdString {
+	let mut suggestions = diagnostics
+		.iter()
+		.flat_map(|diagnostic| &diagnostic.suggestions)
+		.filter(|suggestion| suggestion.applicability == Applicability::MachineApplicable)
+		.collect::<Vec<_>>();
+	suggestions.sort_by_key(|suggestion| (suggestion.span.start, suggestion.span.end));
+
+	let mut fixed = stdString::with_capacity(source.len());
+	let mut copied = 0;
+	for suggestion in suggestions {
+		// Insertions at the end of an applied replacement are fine, overlaps are not.
+		if suggestion.span.start < copied {
+			continue;
+		}
+		fixed.push_str(&source[copied..suggestion.span.start]);
+		fixed.push_str(&suggestion.replacement);
+		copied = suggestion.span.end;
+	}
+	fixed.push_str(&source[copied..]);
+	fixed
+}
+
 pub trait Reporter<Position> {
 	fn report_with(&mut self, diagnostic: impl FnOnce() -> Diagnostic<Position>) {
 		self.report_many_with(|| iter::once_with(diagnostic))
@@ -356,3 +539,180 @@ impl<Position> Reporter<Position> for Vec<Diagnostic<Position>> {
 		self.extend(diagnostics())
 	}
 }
+
+/// Configures the [`DiagnosticLevel`] of individual [`DiagnosticType`]s,
+/// for example to promote a warning to an error or to [`Allow`](`DiagnosticLevel::Allow`) a diagnostic entirely.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeverityMap {
+	levels: Vec<(DiagnosticType, DiagnosticLevel)>,
+}
+
+impl SeverityMap {
+	/// Creates an empty [`SeverityMap`], which keeps each [`DiagnosticType`]'s default level.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the level of diagnostics of `type_` to `level`.
+	pub fn set(&mut self, type_: DiagnosticType, level: DiagnosticLevel) -> &mut Self {
+		match self.levels.iter_mut().find(|(t, _)| *t == type_) {
+			Some((_, existing)) => *existing = level,
+			None => self.levels.push((type_, level)),
+		}
+		self
+	}
+
+	/// The configured level for diagnostics of `type_`, if any.
+	#[must_use]
+	pub fn get(&self, type_: DiagnosticType) -> Option<DiagnosticLevel> {
+		self.levels
+			.iter()
+			.find_map(|(t, level)| (*t == type_).then(|| *level))
+	}
+
+	/// The level diagnostics of `type_` are reported with.
+	#[must_use]
+	pub fn level(&self, type_: DiagnosticType) -> DiagnosticLevel {
+		self.get(type_).unwrap_or(type_.level)
+	}
+
+	/// Wraps `reporter` so that diagnostics are forwarded with their configured level.
+	///
+	/// [Allowed](`DiagnosticLevel::Allow`) diagnostics are dropped instead.
+	pub fn wrap<R>(&self, reporter: R) -> SeverityReporter<'_, R> {
+		SeverityReporter {
+			severities: self,
+			reporter,
+		}
+	}
+}
+
+/// A [`Reporter`] that applies a [`SeverityMap`] before forwarding diagnostics, created by [`SeverityMap::wrap`].
+#[derive(Debug)]
+pub struct SeverityReporter<'a, R> {
+	severities: &'a SeverityMap,
+	reporter: R,
+}
+
+impl<R> SeverityReporter<'_, R> {
+	/// Unwraps the inner [`Reporter`].
+	pub fn into_inner(self) -> R {
+		self.reporter
+	}
+}
+
+impl<Position, R: Reporter<Position>> Reporter<Position> for SeverityReporter<'_, R> {
+	fn report_many_with<I: IntoIterator<Item = Diagnostic<Position>>>(
+		&mut self,
+		diagnostics: impl FnOnce() -> I,
+	) {
+		let severities = self.severities;
+		self.reporter.report_many_with(|| {
+			diagnostics().into_iter().filter_map(move |mut diagnostic| {
+				if let Some(level) = severities.get(diagnostic.type_) {
+					diagnostic.level_override = Some(level);
+				}
+				(diagnostic.level() != DiagnosticLevel::Allow).then(|| diagnostic)
+			})
+		});
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn all_types() {
+	macro_rules! assert_all {
+		($($variant:ident),*$(,)?) => {
+			// Exhaustive, so that new variants must be listed here and, through the assertion, in `ALL`.
+			let name = |type_: DiagnosticType| match type_ {
+				$(DiagnosticType::$variant => stringify!($variant),)*
+			};
+			assert_eq!(
+				DiagnosticType::ALL.iter().copied().map(name).collect::<Vec<_>>(),
+				[$(stringify!($variant)),*],
+			);
+		};
+	}
+	assert_all!(
+		UnrecognizedToken,
+		ZeroPrefixedDecimalFound,
+		ZeroPrefixedIntegerFound,
+		VerbatimCarriageReturnInsideLiteral,
+		HeadingTooDeep,
+		SubsectionInTabularSection,
+		MissingVariantIdentifier,
+		ExpectedKeyValuePair,
+		ExpectedValue,
+		UnclosedList,
+		ValuesLineTooShort,
+		ExpectedListIdentifier,
+		ExpectedTabularPathSegment,
+		UnclosedTabularPathMultiSegment,
+		ExpectedPathSegment,
+		InvalidPathContinuation,
+		KeyPreviouslyDefined,
+		UnclosedListKey,
+		UnclosedTabularPathSection,
+		DuplicateEnumInstantiation,
+		NonMapValueSelected,
+		MisplacedComment,
+		MisplacedHeading,
+		MisplacedData,
+		NonListValueSelected,
+		TupleVariantValueNotAList,
+		CustomErrorFromVisitor,
+		InvalidType,
+		InvalidValue,
+		InvalidLength,
+		UnknownVariant,
+		UnknownField,
+		MissingField,
+		UnknownEncoding,
+		EncodeFailed,
+	);
+	assert_eq!(
+		DiagnosticType::from_code("TAML-D0003"),
+		Some(DiagnosticType::InvalidLength)
+	);
+}
+
+#[cfg(test)]
+#[test]
+fn severities() {
+	use crate::parse;
+	use logos::Logos as _;
+
+	assert_eq!(
+		DiagnosticType::from_code("TAML-P0013"),
+		Some(DiagnosticType::KeyPreviouslyDefined)
+	);
+	assert_eq!(
+		DiagnosticType::from_code("L2"),
+		Some(DiagnosticType::ZeroPrefixedIntegerFound)
+	);
+	assert_eq!(DiagnosticType::from_code("X0013"), None);
+
+	let mut severities = SeverityMap::new();
+	severities
+		.set(
+			DiagnosticType::KeyPreviouslyDefined,
+			DiagnosticLevel::Warning,
+		)
+		.set(
+			DiagnosticType::ZeroPrefixedIntegerFound,
+			DiagnosticLevel::Allow,
+		);
+	assert_eq!(
+		severities.level(DiagnosticType::UnclosedList),
+		DiagnosticLevel::Error
+	);
+
+	let mut reporter = severities.wrap(vec![]);
+	parse(crate::Token::lexer("a: 1\na: 2\n").spanned(), &mut reporter).unwrap_err();
+	parse(crate::Token::lexer("b: 01\n").spanned(), &mut reporter).unwrap_err();
+	let diagnostics = reporter.into_inner();
+	assert_eq!(diagnostics.len(), 1);
+	assert_eq!(diagnostics[0].type_, DiagnosticType::KeyPreviouslyDefined);
+	assert_eq!(diagnostics[0].level(), DiagnosticLevel::Warning);
+}
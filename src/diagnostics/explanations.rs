@@ -0,0 +1,783 @@
+//! Long-form explanations for each [`DiagnosticType`], for example to implement an `--explain` option.
+//!
+//! Explanations are written in Markdown. Each describes what triggers the diagnostic,
+//! usually shows an erroneous example in a `taml` code block and then explains how to fix it.
+
+use super::{parse_code, DiagnosticType};
+
+/// Looks up the explanations of the diagnostics with `code`, with or without `TAML-` prefix (e.g. `"TAML-P0013"` or `"P0013"`).
+///
+/// This is usually one explanation, or none for an unknown code,
+/// but TAML-P0015 is shared by [`UnclosedTabularPathSection`](`DiagnosticType::UnclosedTabularPathSection`)
+/// and [`DuplicateEnumInstantiation`](`DiagnosticType::DuplicateEnumInstantiation`).
+#[must_use]
+pub fn explain(code: &str) -> Vec<&'static str> {
+	parse_code(code).map_or_else(Vec::new, |code| {
+		DiagnosticType::ALL
+			.iter()
+			.filter(|type_| type_.code_parts() == code)
+			.map(|type_| type_.explanation())
+			.collect()
+	})
+}
+
+impl DiagnosticType {
+	/// A long-form explanation of this diagnostic in Markdown.
+	#[must_use]
+	#[allow(clippy::too_many_lines)]
+	pub fn explanation(self) -> &'static str {
+		match self {
+			DiagnosticType::UnrecognizedToken => {
+				r"The lexer found text that isn't part of any TAML token.
+
+Erroneous example:
+
+```taml
+@a: 1
+```
+
+Check for typos or remove the unexpected characters. Text must be quoted:
+
+```taml
+`@a`: 1
+```
+"
+			}
+
+			DiagnosticType::ZeroPrefixedDecimalFound => {
+				r"A decimal was written with a leading zero before its integer part.
+
+Erroneous example:
+
+```taml
+a: 01.5
+```
+
+TAML does not support optional zero prefixes on numbers, as they could be confused with octal literals.
+Remove the leading zeros:
+
+```taml
+a: 1.5
+```
+"
+			}
+
+			DiagnosticType::ZeroPrefixedIntegerFound => {
+				r"An integer was written with a leading zero.
+
+Erroneous example:
+
+```taml
+a: 007
+```
+
+TAML does not support optional zero prefixes on numbers, as they could be confused with octal literals.
+Remove the leading zeros:
+
+```taml
+a: 7
+```
+"
+			}
+
+			DiagnosticType::VerbatimCarriageReturnInsideLiteral => {
+				r#"A quoted literal (a string, quoted identifier or data literal) contains a verbatim U+000D CARRIAGE RETURN (CR).
+
+This usually happens when a multi-line string is saved with Windows line endings.
+Since CR is invisible in most editors, it isn't allowed verbatim inside quotes.
+
+Either delete the carriage return or escape it as `\r`:
+
+```taml
+a: "first line\r
+second line"
+```
+"#
+			}
+
+			DiagnosticType::HeadingTooDeep => {
+				r"A heading is nested more than one level deeper than the previous one.
+
+Erroneous example:
+
+```taml
+# a
+### b
+c: 1
+```
+
+Each heading may have at most one more `#` than the one before it.
+Add the missing intermediate heading, or use a path:
+
+```taml
+# a
+## b
+c: 1
+```
+"
+			}
+
+			DiagnosticType::SubsectionInTabularSection => {
+				r"A heading is nested inside a tabular section.
+
+Erroneous example:
+
+```taml
+# [[a]]
+1
+## b
+```
+
+Tabular sections contain only rows, up to the next heading of the same or lower depth.
+Use a heading of the same depth to continue with another section:
+
+```taml
+# [[a]]
+1
+# b
+```
+"
+			}
+
+			DiagnosticType::MissingVariantIdentifier => {
+				r"A colon in a path is not followed by a variant identifier.
+
+Erroneous example:
+
+```taml
+# a:
+b: 1
+```
+
+In a heading, `:Variant` selects a structured enum variant for the preceding key.
+Add the variant identifier, or remove the colon if the key should be a plain structure:
+
+```taml
+# a:Variant
+b: 1
+```
+"
+			}
+
+			DiagnosticType::ExpectedKeyValuePair => {
+				r"A line in a structured section is not a key-value pair.
+
+Erroneous example:
+
+```taml
+a 1
+```
+
+Structured sections can only contain subsections and key-value pairs, which consist of an identifier,
+a colon and a value:
+
+```taml
+a: 1
+```
+"
+			}
+
+			DiagnosticType::ExpectedValue => {
+				r"A value was expected, but something else was found.
+
+Erroneous example:
+
+```taml
+a: )
+```
+
+Values are strings, numbers, data literals, lists or enum variants:
+
+```taml
+a: ()
+```
+"
+			}
+
+			DiagnosticType::UnclosedList => {
+				r"An inline list is not closed before the end of the line.
+
+Erroneous example:
+
+```taml
+a: (1, 2
+```
+
+Inline lists can't contain line breaks. Close the list with `)`:
+
+```taml
+a: (1, 2)
+```
+"
+			}
+
+			DiagnosticType::ValuesLineTooShort => {
+				r"A table row has fewer values than the table heading has columns.
+
+Erroneous example:
+
+```taml
+# [[a].{b, c}]
+1
+```
+
+Add the missing values, separated by commas:
+
+```taml
+# [[a].{b, c}]
+1, 2
+```
+"
+			}
+
+			DiagnosticType::ExpectedListIdentifier => {
+				r"Square brackets in a column of a tabular heading don't contain exactly one identifier.
+
+Erroneous example:
+
+```taml
+# [[a].{[1]}]
+2
+```
+
+`[key]` appends each row's value to the list `key`. Write the list's key between the brackets:
+
+```taml
+# [[a].{[b]}]
+2
+```
+"
+			}
+
+			DiagnosticType::ExpectedTabularPathSegment => {
+				r"A tabular heading contains something other than a path segment.
+
+Erroneous example:
+
+```taml
+# [[a].{1}]
+1
+```
+
+Each column of a table is named by a path of identifiers, list keys (`[key]`) and nested columns (`{…}`):
+
+```taml
+# [[a].{b}]
+1
+```
+"
+			}
+
+			DiagnosticType::UnclosedTabularPathMultiSegment => {
+				r"A set of columns in a tabular heading (`{…}`) is not closed.
+
+Erroneous example:
+
+```taml
+# [[a].{b, c]
+1, 2
+```
+
+Close the column set with `}`:
+
+```taml
+# [[a].{b, c}]
+1, 2
+```
+"
+			}
+
+			DiagnosticType::ExpectedPathSegment => {
+				r"A heading contains something other than a path segment.
+
+Erroneous example:
+
+```taml
+# a.1
+b: 1
+```
+
+Path segments are identifiers, list keys (`[key]`) or a tabular section (`[[…]]`):
+
+```taml
+# a.b
+b: 1
+```
+"
+			}
+
+			DiagnosticType::InvalidPathContinuation => {
+				r"A path segment in a heading is followed by something other than a period or the end of the line.
+
+Erroneous example:
+
+```taml
+# a b
+c: 1
+```
+
+Separate path segments with periods:
+
+```taml
+# a.b
+c: 1
+```
+"
+			}
+
+			DiagnosticType::KeyPreviouslyDefined => {
+				r"A key is assigned a value more than once.
+
+Erroneous example:
+
+```taml
+a: 1
+a: 2
+```
+
+Keys must be unique within their structure. Remove or rename one of the assignments:
+
+```taml
+a: 1
+b: 2
+```
+
+Items can still be appended to an existing list with `[key]` headings.
+"
+			}
+
+			DiagnosticType::UnclosedListKey => {
+				r"A list key in a heading (`[key]`) is not closed.
+
+Erroneous example:
+
+```taml
+# [a
+b: 1
+```
+
+Close the list key with `]`:
+
+```taml
+# [a]
+b: 1
+```
+"
+			}
+
+			DiagnosticType::UnclosedTabularPathSection => {
+				r"A tabular section in a heading (`[[…]]`) is not closed.
+
+Erroneous example:
+
+```taml
+# [[a]
+1
+```
+
+Close the tabular section with `]`:
+
+```taml
+# [[a]]
+1
+```
+"
+			}
+
+			DiagnosticType::DuplicateEnumInstantiation => {
+				r"A heading instantiates an enum variant for a key that already has a value.
+
+Erroneous example:
+
+```taml
+# a:Variant
+b: 1
+
+# a:Variant
+c: 2
+```
+
+A structured variant can only be instantiated once. Merge the sections:
+
+```taml
+# a:Variant
+b: 1
+c: 2
+```
+"
+			}
+
+			DiagnosticType::NonMapValueSelected => {
+				r"A heading selects a key as structure, but that key already has a different value.
+
+Erroneous example:
+
+```taml
+a: 1
+
+# a
+b: 2
+```
+
+Rename one of the keys, or remove the earlier value:
+
+```taml
+# a
+b: 2
+```
+"
+			}
+
+			DiagnosticType::MisplacedComment => {
+				r"A comment directly follows another comment.
+
+This diagnostic indicates a bug in the lexer or parser, as a comment always extends to the end of its line.
+Please report it along with the input that caused it.
+"
+			}
+
+			DiagnosticType::MisplacedHeading => {
+				r"A heading does not start its own line.
+
+Erroneous example:
+
+```taml
+a: 1 # b
+```
+
+Headings must be on a line of their own. Comments start with `//`:
+
+```taml
+a: 1 // b
+```
+"
+			}
+
+			DiagnosticType::MisplacedData => {
+				r"A key-value pair or table row is followed by more data on the same line.
+
+Erroneous example:
+
+```taml
+a: 1 b: 2
+```
+
+Put each key-value pair on its own line, and separate values in table rows with commas:
+
+```taml
+a: 1
+b: 2
+```
+"
+			}
+
+			DiagnosticType::NonListValueSelected => {
+				r"A heading appends to a key as list, but that key already has a value that isn't a list.
+
+Erroneous example:
+
+```taml
+a: 1
+
+# [a]
+b: 2
+```
+
+Rename one of the keys, or remove the earlier value:
+
+```taml
+# [a]
+b: 2
+```
+"
+			}
+
+			DiagnosticType::TupleVariantValueNotAList => {
+				r"A table column is declared as enum variant (`key:Variant`), but its value is not a list.
+
+Erroneous example:
+
+```taml
+# [[a].{b:Variant}]
+1
+```
+
+The column's values are the variant's fields, so they must be written as list:
+
+```taml
+# [[a].{b:Variant}]
+(1)
+```
+"
+			}
+
+			DiagnosticType::CustomErrorFromVisitor => {
+				r"The type being deserialised rejected the input with a custom error.
+
+The diagnostic's labels describe the problem in detail.
+"
+			}
+
+			DiagnosticType::InvalidType => {
+				r#"A value has a different type than expected.
+
+For example, deserialising the following into a structure with a `u8` field `a` fails,
+as `"1"` is a string:
+
+```taml
+a: "1"
+```
+
+Note that integers and decimals are disjoint: Write `1.0` if a decimal is expected.
+"#
+			}
+
+			DiagnosticType::InvalidValue => {
+				r"A value has the expected type, but is not valid.
+
+For example, deserialising the following into a structure with a `u8` field `a` fails,
+as `256` is out of range:
+
+```taml
+a: 256
+```
+"
+			}
+
+			DiagnosticType::InvalidLength => {
+				r"A list or structure has an unexpected number of elements.
+
+For example, deserialising the following into a structure with a `(u8, u8)` field `a` fails:
+
+```taml
+a: (1, 2, 3)
+```
+"
+			}
+
+			DiagnosticType::UnknownVariant => {
+				r"An enum value names a variant that the target enum doesn't have.
+
+For example, deserialising the following into a structure with a field `a` fails
+if `a`'s enum type has no variant `Tree`:
+
+```taml
+a: Tree
+```
+
+Check the spelling of the variant. Variant names are case-sensitive.
+"
+			}
+
+			DiagnosticType::UnknownField => {
+				r"A structure contains a field that the target type doesn't have.
+
+For example, deserialising the following into a structure that only has a field `a`
+and denies unknown fields fails:
+
+```taml
+a: 1
+b: 2
+```
+
+Check the spelling of the field's key, or remove it.
+"
+			}
+
+			DiagnosticType::MissingField => {
+				r"A structure is missing a field that the target type requires.
+
+For example, deserialising the following into a structure with the fields `a` and `b` fails:
+
+```taml
+a: 1
+```
+
+Add a key-value pair (or section) for the missing field.
+"
+			}
+
+			DiagnosticType::UnknownEncoding => {
+				r"A data literal uses an encoding the target type doesn't support.
+
+For example, deserialising the following into a byte buffer fails unless `Base99` is a supported encoding:
+
+```taml
+a: <Base99:…>
+```
+
+Check the spelling of the encoding, or use one of those listed in the diagnostic.
+"
+			}
+
+			DiagnosticType::EncodeFailed => {
+				r"A data literal's contents are not valid in its encoding.
+
+For example, deserialising the following into a byte buffer fails, as `g` is not a hexadecimal digit:
+
+```taml
+a: <hex:0g>
+```
+
+The diagnostic points at the invalid part of the literal.
+"
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+#[allow(clippy::too_many_lines)]
+fn examples() {
+	use crate::{
+		diagnostics::{Diagnostic, DiagnosticGroup},
+		parsing::{parse_with, ParseMode},
+		Token,
+	};
+	use logos::Logos as _;
+
+	fn parse(example: &str) -> Vec<Diagnostic<usize>> {
+		let mut diagnostics = vec![];
+		parse_with(
+			Token::lexer(example).spanned(),
+			&mut diagnostics,
+			ParseMode::Recovering,
+		);
+		diagnostics
+	}
+
+	fn assert_triggers(type_: DiagnosticType, diagnostics: &[Diagnostic<usize>]) {
+		assert!(
+			diagnostics
+				.iter()
+				.any(|diagnostic| diagnostic.type_ == type_),
+			"{:?} is not triggered by its example, which reports {:?}.",
+			type_,
+			diagnostics,
+		);
+	}
+
+	/// Deserialises `example` into the target type its explanation describes.
+	#[cfg(feature = "serde")]
+	fn deserialise(type_: DiagnosticType, example: &str) -> Vec<Diagnostic<usize>> {
+		use serde::Deserialize;
+
+		#[derive(Deserialize)]
+		#[serde(deny_unknown_fields)]
+		#[allow(dead_code)]
+		struct Number {
+			a: u8,
+		}
+
+		#[derive(Deserialize)]
+		#[allow(dead_code)]
+		struct Pair {
+			a: (u8, u8),
+		}
+
+		#[derive(Deserialize)]
+		#[allow(dead_code)]
+		struct Numbers {
+			a: u8,
+			b: u8,
+		}
+
+		#[derive(Deserialize)]
+		enum Kind {
+			Leaf,
+		}
+
+		#[derive(Deserialize)]
+		#[allow(dead_code)]
+		struct Variant {
+			a: Kind,
+		}
+
+		#[derive(Deserialize)]
+		#[allow(dead_code)]
+		struct Bytes {
+			a: Vec<u8>,
+		}
+
+		let mut diagnostics = vec![];
+		let _ = match type_ {
+			DiagnosticType::InvalidType
+			| DiagnosticType::InvalidValue
+			| DiagnosticType::UnknownField => crate::from_str::<Number>(example, &mut diagnostics).map(drop),
+			DiagnosticType::InvalidLength => {
+				crate::from_str::<Pair>(example, &mut diagnostics).map(drop)
+			}
+			DiagnosticType::MissingField => {
+				crate::from_str::<Numbers>(example, &mut diagnostics).map(drop)
+			}
+			DiagnosticType::UnknownVariant => {
+				crate::from_str::<Variant>(example, &mut diagnostics).map(drop)
+			}
+			DiagnosticType::UnknownEncoding | DiagnosticType::EncodeFailed => {
+				crate::from_str::<Bytes>(example, &mut diagnostics).map(drop)
+			}
+			_ => panic!("There is no target type for {:?}'s example.", type_),
+		};
+		diagnostics
+	}
+
+	let without_example = [
+		// A verbatim carriage return is invisible, so only the fixed example is shown.
+		DiagnosticType::VerbatimCarriageReturnInsideLiteral,
+		// Unreachable, as a comment always extends to the end of its line.
+		DiagnosticType::MisplacedComment,
+		// Only reported by `Deserialize` implementations, so no input triggers it on its own.
+		DiagnosticType::CustomErrorFromVisitor,
+	];
+
+	for &type_ in DiagnosticType::ALL {
+		let code = type_.code();
+		if type_ == DiagnosticType::DuplicateEnumInstantiation {
+			// Shares TAML-P0015 with the earlier `UnclosedTabularPathSection`.
+			assert_eq!(
+				DiagnosticType::from_code(&code),
+				Some(DiagnosticType::UnclosedTabularPathSection)
+			);
+			assert_eq!(
+				explain(&code),
+				[
+					DiagnosticType::UnclosedTabularPathSection.explanation(),
+					type_.explanation(),
+				]
+			);
+		} else {
+			assert_eq!(
+				DiagnosticType::from_code(&code),
+				Some(type_),
+				"{} is not unique.",
+				code
+			);
+		}
+		assert!(explain(&code).contains(&type_.explanation()));
+
+		let mut examples = type_
+			.explanation()
+			.split("```taml\n")
+			.skip(1)
+			.map(|block| block.split_once("```").unwrap().0);
+
+		if !without_example.contains(&type_) {
+			let erroneous = examples
+				.next()
+				.unwrap_or_else(|| panic!("{:?} has no example.", type_));
+			if matches!(type_.group, DiagnosticGroup::Deserialising) {
+				// The example is valid TAML and only fails to deserialise into a specific type.
+				assert_eq!(parse(erroneous), [], "Example of {:?} is invalid.", type_);
+				#[cfg(feature = "serde")]
+				assert_triggers(type_, &deserialise(type_, erroneous));
+			} else {
+				assert_triggers(type_, &parse(erroneous));
+			}
+		}
+
+		for fixed in examples {
+			assert_eq!(parse(fixed), [], "Fixed example of {:?} is invalid.", type_);
+		}
+	}
+}
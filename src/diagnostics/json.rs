@@ -0,0 +1,315 @@
+//! Serialises [`Diagnostic`]s into a stable JSON schema for consumption by other tools.
+//!
+//! A diagnostic is written as:
+//!
+//! ```json
+//! {
+//!   "code": "TAML-P0013",
+//!   "type": "KeyPreviouslyDefined",
+//!   "title": "Key previously defined",
+//!   "level": "error",
+//!   "labels": [
+//!     {
+//!       "caption": "This key has already been assigned a value.",
+//!       "priority": "primary",
+//!       "span": {
+//!         "start": { "offset": 5, "line": 2, "column": 1 },
+//!         "end": { "offset": 6, "line": 2, "column": 2 }
+//!       }
+//!     }
+//!   ],
+//!   "suggestions": []
+//! }
+//! ```
+//!
+//! (without insignificant whitespace). `offset`s are byte offsets into the source,
+//! `line`s and `column`s are 1-based and `column`s are counted in [`char`]s.
+//! Missing captions and spans are written as `null`.
+//! `"level"` is the level the diagnostic is reported with, i.e. its [`level_override`](`Diagnostic::level_override`) if set.
+//!
+//! Each suggestion is an object with the fields `"message"`, `"applicability"`, `"span"` (as above) and `"replacement"`.
+//! `"applicability"` is one of `"machine-applicable"`, `"maybe-incorrect"` and `"has-placeholders"`.
+//!
+//! New fields may be added in future versions, but existing ones will not change their meaning.
+
+use super::{
+	Applicability, Diagnostic, DiagnosticLabel, DiagnosticLabelPriority, DiagnosticLevel,
+	DiagnosticType, Suggestion,
+};
+use crate::LineIndex;
+use std::{
+	fmt::{self, Write},
+	ops::Range,
+};
+
+/// Serialises `diagnostics` as JSON array into a [`String`].
+///
+/// `source` is the text the diagnostics' spans refer to.
+///
+/// # Panics
+///
+/// Iff a label's span is out of bounds or not on [`char`] boundaries in `source`.
+#[must_use]
+pub fn to_string(source: &str, diagnostics: &[Diagnostic<usize>]) -> String {
+	let mut output = String::new();
+	write(&mut output, source, diagnostics).expect("infallible");
+	output
+}
+
+/// Serialises `diagnostics` as JSON array into `output`.
+///
+/// `source` is the text the diagnostics' spans refer to.
+///
+/// # Errors
+///
+/// Iff writing to `output` fails.
+///
+/// # Panics
+///
+/// Iff a label's span is out of bounds or not on [`char`] boundaries in `source`.
+pub fn write(
+	output: &mut impl Write,
+	source: &str,
+	diagnostics: &[Diagnostic<usize>],
+) -> fmt::Result {
+	let lines = LineIndex::new(source);
+	output.write_char('[')?;
+	for (i, diagnostic) in diagnostics.iter().enumerate() {
+		if i > 0 {
+			output.write_char(',')?;
+		}
+		write_diagnostic_with(output, &lines, diagnostic)?;
+	}
+	output.write_char(']')
+}
+
+/// Serialises a single `diagnostic` as JSON object into `output`.
+///
+/// # Errors
+///
+/// Iff writing to `output` fails.
+///
+/// # Panics
+///
+/// Iff a label's span is out of bounds or not on [`char`] boundaries in `source`.
+pub fn write_diagnostic(
+	output: &mut impl Write,
+	source: &str,
+	diagnostic: &Diagnostic<usize>,
+) -> fmt::Result {
+	write_diagnostic_with(output, &LineIndex::new(source), diagnostic)
+}
+
+/// Serialises `type_` as JSON object with the fields `"code"`, `"type"`, `"title"` and `"level"`.
+///
+/// These are the same fields that appear at the top of a serialised [`Diagnostic`],
+/// except that `"level"` is always the type's default level here.
+///
+/// # Errors
+///
+/// Iff writing to `output` fails.
+pub fn write_type(output: &mut impl Write, type_: DiagnosticType) -> fmt::Result {
+	output.write_char('{')?;
+	write_type_fields(output, type_, type_.level)?;
+	output.write_char('}')
+}
+
+/// Serialises `label` as JSON object into `output`.
+///
+/// # Errors
+///
+/// Iff writing to `output` fails.
+///
+/// # Panics
+///
+/// Iff a label's span is out of bounds or not on [`char`] boundaries in `source`.
+pub fn write_label(
+	output: &mut impl Write,
+	source: &str,
+	label: &DiagnosticLabel<usize>,
+) -> fmt::Result {
+	write_label_with(output, &LineIndex::new(source), label)
+}
+
+/// Serialises `level` as JSON string: `"allow"`, `"help"`, `"note"`, `"warning"` or `"error"`.
+///
+/// # Errors
+///
+/// Iff writing to `output` fails.
+pub fn write_level(output: &mut impl Write, level: DiagnosticLevel) -> fmt::Result {
+	write_string(output, level_name(level))
+}
+
+pub(super) fn level_name(level: DiagnosticLevel) -> &'static str {
+	match level {
+		DiagnosticLevel::Allow => "allow",
+		DiagnosticLevel::Help => "help",
+		DiagnosticLevel::Note => "note",
+		DiagnosticLevel::Warning => "warning",
+		DiagnosticLevel::Error => "error",
+	}
+}
+
+fn write_diagnostic_with(
+	output: &mut impl Write,
+	lines: &LineIndex,
+	diagnostic: &Diagnostic<usize>,
+) -> fmt::Result {
+	output.write_char('{')?;
+	write_type_fields(output, diagnostic.type_, diagnostic.level())?;
+	output.write_str(",\"labels\":[")?;
+	for (i, label) in diagnostic.labels.iter().enumerate() {
+		if i > 0 {
+			output.write_char(',')?;
+		}
+		write_label_with(output, lines, label)?;
+	}
+	output.write_str("],\"suggestions\":[")?;
+	for (i, suggestion) in diagnostic.suggestions.iter().enumerate() {
+		if i > 0 {
+			output.write_char(',')?;
+		}
+		write_suggestion_with(output, lines, suggestion)?;
+	}
+	output.write_str("]}")
+}
+
+fn write_type_fields(
+	output: &mut impl Write,
+	type_: DiagnosticType,
+	level: DiagnosticLevel,
+) -> fmt::Result {
+	output.write_str("\"code\":")?;
+	write_string(output, &type_.code())?;
+	output.write_str(",\"type\":")?;
+	write_string(output, &format!("{:?}", type_))?;
+	output.write_str(",\"title\":")?;
+	write_string(output, type_.title)?;
+	output.write_str(",\"level\":")?;
+	write_level(output, level)
+}
+
+fn write_label_with(
+	output: &mut impl Write,
+	lines: &LineIndex,
+	label: &DiagnosticLabel<usize>,
+) -> fmt::Result {
+	output.write_str("{\"caption\":")?;
+	match &label.caption {
+		Some(caption) => write_string(output, caption)?,
+		None => output.write_str("null")?,
+	}
+	output.write_str(",\"priority\":")?;
+	write_string(
+		output,
+		match label.priority {
+			DiagnosticLabelPriority::Primary => "primary",
+			DiagnosticLabelPriority::Auxiliary => "auxiliary",
+		},
+	)?;
+	output.write_str(",\"span\":")?;
+	match &label.span {
+		Some(span) => write_span(output, lines, span)?,
+		None => output.write_str("null")?,
+	}
+	output.write_char('}')
+}
+
+fn write_suggestion_with(
+	output: &mut impl Write,
+	lines: &LineIndex,
+	suggestion: &Suggestion<usize>,
+) -> fmt::Result {
+	output.write_str("{\"message\":")?;
+	write_string(output, &suggestion.message)?;
+	output.write_str(",\"applicability\":")?;
+	write_string(
+		output,
+		match suggestion.applicability {
+			Applicability::MachineApplicable => "machine-applicable",
+			Applicability::MaybeIncorrect => "maybe-incorrect",
+			Applicability::HasPlaceholders => "has-placeholders",
+		},
+	)?;
+	output.write_str(",\"span\":")?;
+	write_span(output, lines, &suggestion.span)?;
+	output.write_str(",\"replacement\":")?;
+	write_string(output, &suggestion.replacement)?;
+	output.write_char('}')
+}
+
+fn write_span(output: &mut impl Write, lines: &LineIndex, span: &Range<usize>) -> fmt::Result {
+	for (name, offset) in [("{\"start\":", span.start), (",\"end\":", span.end)] {
+		let position = lines.line_column(offset);
+		let (line, column) = (position.line + 1, position.char_column + 1);
+		output.write_str(name)?;
+		write!(
+			output,
+			"{{\"offset\":{},\"line\":{},\"column\":{}}}",
+			offset, line, column,
+		)?;
+	}
+	output.write_char('}')
+}
+
+/// Writes `value` as quoted and escaped JSON string.
+pub(super) fn write_string(output: &mut impl Write, value: &str) -> fmt::Result {
+	output.write_char('"')?;
+	for c in value.chars() {
+		match c {
+			'"' => output.write_str("\\\"")?,
+			'\\' => output.write_str("\\\\")?,
+			'\n' => output.write_str("\\n")?,
+			'\r' => output.write_str("\\r")?,
+			'\t' => output.write_str("\\t")?,
+			c if c < ' ' => write!(output, "\\u{:04x}", c as u32)?,
+			c => output.write_char(c)?,
+		}
+	}
+	output.write_char('"')
+}
+
+#[cfg(test)]
+#[test]
+fn json() {
+	let source = "a: 1\n\tä: \"\n";
+	let diagnostic = Diagnostic {
+		suggestions: vec![Suggestion::new(
+			"Fix.",
+			2..2,
+			"\t",
+			Applicability::MaybeIncorrect,
+		)],
+		..Diagnostic::new(
+			DiagnosticType::KeyPreviouslyDefined,
+			vec![
+				DiagnosticLabel::new("\"Quoted\"\n\\", 6..8, DiagnosticLabelPriority::Primary),
+				DiagnosticLabel {
+					caption: None,
+					span: None,
+					priority: DiagnosticLabelPriority::Auxiliary,
+				},
+			],
+		)
+	};
+	assert_eq!(
+		to_string(source, &[diagnostic]),
+		r#"[{"code":"TAML-P0013","type":"KeyPreviouslyDefined","title":"Key previously defined","level":"error","labels":[{"caption":"\"Quoted\"\n\\","priority":"primary","span":{"start":{"offset":6,"line":2,"column":2},"end":{"offset":8,"line":2,"column":3}}},{"caption":null,"priority":"auxiliary","span":null}],"suggestions":[{"message":"Fix.","applicability":"maybe-incorrect","span":{"start":{"offset":2,"line":1,"column":3},"end":{"offset":2,"line":1,"column":3}},"replacement":"\t"}]}]"#
+	);
+	assert_eq!(to_string(source, &[]), "[]");
+
+	let overridden = Diagnostic {
+		level_override: Some(DiagnosticLevel::Warning),
+		..Diagnostic::new(DiagnosticType::KeyPreviouslyDefined, vec![])
+	};
+	assert_eq!(
+		to_string(source, &[overridden]),
+		r#"[{"code":"TAML-P0013","type":"KeyPreviouslyDefined","title":"Key previously defined","level":"warning","labels":[],"suggestions":[]}]"#
+	);
+	let mut type_ = String::new();
+	write_type(&mut type_, DiagnosticType::KeyPreviouslyDefined).unwrap();
+	assert_eq!(
+		type_,
+		r#"{"code":"TAML-P0013","type":"KeyPreviouslyDefined","title":"Key previously defined","level":"error"}"#
+	);
+}
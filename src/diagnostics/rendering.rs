@@ -0,0 +1,471 @@
+//! Renders [`Diagnostic`]s as human-readable, annotated source snippets in the style of `rustc`.
+//!
+//! [`Suggestion`](`super::Suggestion`)s are listed as `help` notes below the snippets.
+//!
+//! ```text
+//! error[TAML-P0013]: Key previously defined
+//!  --> config.taml:2:1
+//!   |
+//! 2 | a: 2
+//!   | ^ This key has already been assigned a value.
+//! ```
+
+use super::{
+	json::level_name, Diagnostic, DiagnosticLabel, DiagnosticLabelPriority, DiagnosticLevel,
+};
+use crate::{FileId, FilePosition, LineIndex, SourceMap};
+use std::{
+	fmt::{self, Write},
+	ops::Range,
+};
+
+/// Options for the functions in this module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenderOptions {
+	/// Whether to colour the output with ANSI escape sequences.
+	pub colour: bool,
+}
+
+/// Tabs are displayed as this many spaces, so that underlines line up.
+const TAB_WIDTH: usize = 4;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const GREEN: &str = "\x1b[1;32m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+
+/// Renders `diagnostic` with the parts of `source` it refers to into a [`String`].
+///
+/// `file_name` is shown alongside line and column numbers.
+///
+/// # Panics
+///
+/// Iff a label's span is out of bounds or not on [`char`] boundaries in `source`.
+#[must_use]
+pub fn to_string(
+	source: &str,
+	file_name: Option<&str>,
+	diagnostic: &Diagnostic<usize>,
+	options: &RenderOptions,
+) -> String {
+	let mut output = String::new();
+	write(&mut output, source, file_name, diagnostic, options).expect("infallible");
+	output
+}
+
+/// Renders `diagnostic` with the parts of `source` it refers to into `output`.
+///
+/// `file_name` is shown alongside line and column numbers.
+///
+/// # Errors
+///
+/// Iff `output` fails.
+///
+/// # Panics
+///
+/// Iff a label's span is out of bounds or not on [`char`] boundaries in `source`.
+pub fn write(
+	output: &mut impl Write,
+	source: &str,
+	file_name: Option<&str>,
+	diagnostic: &Diagnostic<usize>,
+	options: &RenderOptions,
+) -> fmt::Result {
+	let lines = LineIndex::new(source);
+	let snippet = Snippet {
+		file_name,
+		lines: &lines,
+		labels: diagnostic
+			.labels
+			.iter()
+			.filter_map(|label| Some((label, label.span.clone()?)))
+			.collect(),
+	};
+	write_snippets(output, diagnostic, vec![snippet], options)
+}
+
+/// Renders `diagnostic` with the parts of the files in `source_map` it refers to into a [`String`].
+///
+/// Each file's snippet is introduced by its name, starting with the file of the first primary label.
+///
+/// # Panics
+///
+/// Iff a label's span refers to a file not in `source_map`,
+/// or is out of bounds or not on [`char`] boundaries in that file.
+#[must_use]
+pub fn to_string_with_source_map(
+	source_map: &SourceMap,
+	diagnostic: &Diagnostic<FilePosition>,
+	options: &RenderOptions,
+) -> String {
+	let mut output = String::new();
+	write_with_source_map(&mut output, source_map, diagnostic, options).expect("infallible");
+	output
+}
+
+/// Renders `diagnostic` with the parts of the files in `source_map` it refers to into `output`.
+///
+/// Each file's snippet is introduced by its name, starting with the file of the first primary label.
+///
+/// # Errors
+///
+/// Iff `output` fails.
+///
+/// # Panics
+///
+/// Iff a label's span refers to a file not in `source_map`,
+/// or is out of bounds or not on [`char`] boundaries in that file.
+pub fn write_with_source_map(
+	output: &mut impl Write,
+	source_map: &SourceMap,
+	diagnostic: &Diagnostic<FilePosition>,
+	options: &RenderOptions,
+) -> fmt::Result {
+	let mut files: Vec<FileId> = vec![];
+	for label in diagnostic
+		.labels
+		.iter()
+		.filter(|label| label.priority == DiagnosticLabelPriority::Primary)
+		.chain(&diagnostic.labels)
+	{
+		if let Some(span) = &label.span {
+			if !files.contains(&span.start.file) {
+				files.push(span.start.file);
+			}
+		}
+	}
+
+	let snippets = files
+		.into_iter()
+		.map(|file| Snippet {
+			file_name: Some(source_map.name(file)),
+			lines: source_map.line_index(file),
+			labels: diagnostic
+				.labels
+				.iter()
+				.filter_map(|label| {
+					let span = label.span.as_ref()?;
+					(span.start.file == file).then(|| {
+						// A span can't sensibly end in another file, so it's collapsed in that case.
+						let end = if span.end.file == file {
+							span.end.offset
+						} else {
+							span.start.offset
+						};
+						(label, span.start.offset..end)
+					})
+				})
+				.collect(),
+		})
+		.collect();
+	write_snippets(output, diagnostic, snippets, options)
+}
+
+/// The labels in one source file, with spans as byte offsets into it.
+struct Snippet<'a, Position> {
+	file_name: Option<&'a str>,
+	lines: &'a LineIndex<'a>,
+	labels: Vec<(&'a DiagnosticLabel<Position>, Range<usize>)>,
+}
+
+fn write_snippets<Position>(
+	output: &mut impl Write,
+	diagnostic: &Diagnostic<Position>,
+	mut snippets: Vec<Snippet<Position>>,
+	options: &RenderOptions,
+) -> fmt::Result {
+	#![allow(clippy::too_many_lines)]
+
+	let paint = |colour: &'static str| if options.colour { colour } else { "" };
+	let reset = paint(RESET);
+	let level_colour = match diagnostic.level() {
+		DiagnosticLevel::Allow => BOLD,
+		DiagnosticLevel::Help => CYAN,
+		DiagnosticLevel::Note => GREEN,
+		DiagnosticLevel::Warning => YELLOW,
+		DiagnosticLevel::Error => RED,
+	};
+	let gutter = paint(BLUE);
+
+	writeln!(
+		output,
+		"{}{}[{}]{reset}{}: {}{reset}",
+		paint(level_colour),
+		level_name(diagnostic.level()),
+		diagnostic.code(),
+		paint(BOLD),
+		diagnostic.message(),
+		reset = reset,
+	)?;
+
+	snippets.retain(|snippet| !snippet.labels.is_empty());
+	let width = snippets
+		.iter()
+		.filter_map(|snippet| {
+			let end = snippet.labels.iter().map(|(_, span)| span.end).max()?;
+			Some(snippet.lines.line(end) + 1)
+		})
+		.max()
+		.unwrap_or(0)
+		.to_string()
+		.len();
+
+	for (
+		i,
+		Snippet {
+			file_name,
+			lines,
+			mut labels,
+		},
+	) in snippets.into_iter().enumerate()
+	{
+		labels.sort_by_key(|(_, span)| (span.start, span.end));
+
+		let (_, location) = labels
+			.iter()
+			.find(|(label, _)| label.priority == DiagnosticLabelPriority::Primary)
+			.unwrap_or(&labels[0]);
+		let location = lines.line_column(location.start);
+		writeln!(
+			output,
+			"{:width$}{gutter}{}{reset} {}{}:{}",
+			"",
+			if i == 0 { "-->" } else { ":::" },
+			file_name.map_or_else(String::new, |file_name| format!("{}:", file_name)),
+			location.line + 1,
+			location.char_column + 1,
+			width = width,
+			gutter = gutter,
+			reset = reset,
+		)?;
+		writeln!(
+			output,
+			"{:width$} {gutter}|{reset}",
+			"",
+			width = width,
+			gutter = gutter,
+			reset = reset,
+		)?;
+
+		let mut annotated_lines = labels
+			.iter()
+			.flat_map(|(_, span)| lines.line(span.start)..=lines.line(span.end))
+			.collect::<Vec<_>>();
+		annotated_lines.sort_unstable();
+		annotated_lines.dedup();
+
+		let mut previous = None;
+		for line in annotated_lines {
+			if matches!(previous, Some(previous) if previous + 1 < line) {
+				writeln!(output, "{}...{}", gutter, reset)?;
+			}
+			previous = Some(line);
+
+			let text = lines.line_text(line).expect("Annotated lines exist.");
+			writeln!(
+				output,
+				"{gutter}{:>width$} |{reset} {}",
+				line + 1,
+				text.replace('\t', &" ".repeat(TAB_WIDTH)),
+				width = width,
+				gutter = gutter,
+				reset = reset,
+			)?;
+
+			for (label, span) in &labels {
+				let (first, last) = (lines.line(span.start), lines.line(span.end));
+				if !(first..=last).contains(&line) {
+					continue;
+				}
+
+				let line_start = lines.line_start(line).expect("Annotated lines exist.");
+				let start = if line == first {
+					span.start - line_start
+				} else {
+					0
+				};
+				let end = if line == last {
+					span.end - line_start
+				} else {
+					text.len()
+				}
+				.min(text.len());
+				let start = start.min(end);
+				let indentation = display_width(&text[..start]);
+				let underline = display_width(&text[start..end]).max(1);
+
+				let (marker, colour) = match label.priority {
+					DiagnosticLabelPriority::Primary => ('^', level_colour),
+					DiagnosticLabelPriority::Auxiliary => ('-', BLUE),
+				};
+				write!(
+					output,
+					"{:width$} {gutter}|{reset} {:indentation$}{}{}",
+					"",
+					"",
+					paint(colour),
+					marker.to_string().repeat(underline),
+					width = width,
+					gutter = gutter,
+					reset = reset,
+					indentation = indentation,
+				)?;
+				if let (true, Some(caption)) = (line == last, &label.caption) {
+					let continuation = format!(
+						"{:width$} {gutter}|{reset}{} {:padding$}",
+						"",
+						paint(colour),
+						"",
+						width = width,
+						gutter = gutter,
+						reset = reset,
+						padding = indentation + underline,
+					);
+					write_caption(output, caption, &continuation)?;
+				}
+				writeln!(output, "{}", reset)?;
+			}
+		}
+	}
+
+	for label in diagnostic
+		.labels
+		.iter()
+		.filter(|label| label.span.is_none())
+	{
+		if let DiagnosticLabel {
+			caption: Some(caption),
+			..
+		} = label
+		{
+			write!(output, "  {}={}", gutter, reset)?;
+			write_caption(output, caption, "    ")?;
+			writeln!(output)?;
+		}
+	}
+
+	for suggestion in &diagnostic.suggestions {
+		write!(output, "  {}={} help:", gutter, reset)?;
+		write_caption(output, &suggestion.message, "          ")?;
+		writeln!(output)?;
+	}
+
+	Ok(())
+}
+
+/// Writes a (potentially multi-line) caption, starting continuation lines with `continuation`.
+fn write_caption(output: &mut impl Write, caption: &str, continuation: &str) -> fmt::Result {
+	for (i, line) in caption.lines().enumerate() {
+		if i > 0 {
+			write!(output, "\n{}", continuation)?;
+		}
+		write!(output, " {}", line)?;
+	}
+	Ok(())
+}
+
+fn display_width(text: &str) -> usize {
+	text.chars()
+		.map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+		.sum()
+}
+
+#[cfg(test)]
+#[test]
+fn render() {
+	use super::{DiagnosticLabel, DiagnosticType};
+
+	let source = "a: 1\n\n\n# b\n\tc: [\n\t\t1,\n\t]\n";
+	let diagnostic = Diagnostic::new(
+		DiagnosticType::KeyPreviouslyDefined,
+		vec![
+			DiagnosticLabel::new(
+				"This key has already been assigned a value.",
+				12..13,
+				DiagnosticLabelPriority::Primary,
+			),
+			DiagnosticLabel::new(
+				"Originally defined here...\n...in a different section.",
+				0..1,
+				DiagnosticLabelPriority::Auxiliary,
+			),
+			DiagnosticLabel::new("Multi-line", 15..24, DiagnosticLabelPriority::Auxiliary),
+			DiagnosticLabel::new(
+				"Keys must be unique per map.",
+				None,
+				DiagnosticLabelPriority::Auxiliary,
+			),
+		],
+	);
+	assert_eq!(
+		to_string(
+			source,
+			Some("config.taml"),
+			&diagnostic,
+			&RenderOptions::default()
+		),
+		"error[TAML-P0013]: Key previously defined
+ --> config.taml:5:2
+  |
+1 | a: 1
+  | - Originally defined here...
+  |   ...in a different section.
+...
+5 |     c: [
+  |     ^ This key has already been assigned a value.
+  |        -
+6 |         1,
+  | ----------
+7 |     ]
+  | ----- Multi-line
+  = Keys must be unique per map.
+"
+	);
+}
+
+#[cfg(test)]
+#[test]
+fn render_with_source_map() {
+	use super::DiagnosticType;
+
+	let mut source_map = SourceMap::new();
+	let first = source_map.add("first.taml", "a: 1\n");
+	let second = source_map.add("second.taml", "\n\n\n\n\n\n\n\n\na: 2\n");
+	let span = |file, offset| {
+		FilePosition { file, offset }..FilePosition {
+			file,
+			offset: offset + 1,
+		}
+	};
+	let diagnostic = Diagnostic::new(
+		DiagnosticType::KeyPreviouslyDefined,
+		vec![
+			DiagnosticLabel::new(
+				"Originally defined here.",
+				span(first, 0),
+				DiagnosticLabelPriority::Auxiliary,
+			),
+			DiagnosticLabel::new(
+				"This key has already been assigned a value.",
+				span(second, 9),
+				DiagnosticLabelPriority::Primary,
+			),
+		],
+	);
+	assert_eq!(
+		to_string_with_source_map(&source_map, &diagnostic, &RenderOptions::default()),
+		"error[TAML-P0013]: Key previously defined
+  --> second.taml:10:1
+   |
+10 | a: 2
+   | ^ This key has already been assigned a value.
+  ::: first.taml:1:1
+   |
+ 1 | a: 1
+   | - Originally defined here.
+"
+	);
+}
@@ -0,0 +1,213 @@
+//! Writes [`Diagnostic`]s as [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) log,
+//! as understood by many code scanning tools.
+//!
+//! The log contains a single run. Each distinct [`DiagnosticType`](`super::DiagnosticType`) becomes a rule
+//! identified by its code (e.g. `TAML-P0013`), each diagnostic a result.
+//! Primary labels become the result's `locations`, auxiliary labels its `relatedLocations`.
+//! Labels without span are appended to the result's message instead.
+//!
+//! Columns are counted in Unicode code points (`"columnKind": "unicodeCodePoints"`).
+
+use super::{
+	json::write_string, Diagnostic, DiagnosticLabel, DiagnosticLabelPriority, DiagnosticLevel,
+	DiagnosticType,
+};
+use crate::LineIndex;
+use std::fmt::{self, Write};
+
+/// Writes `diagnostics` for the file at `uri` as SARIF log into a [`String`].
+///
+/// `source` is the text the diagnostics' spans refer to.
+///
+/// # Panics
+///
+/// Iff a label's span is out of bounds or not on [`char`] boundaries in `source`.
+#[must_use]
+pub fn to_string(uri: &str, source: &str, diagnostics: &[Diagnostic<usize>]) -> String {
+	let mut output = String::new();
+	write(&mut output, uri, source, diagnostics).expect("infallible");
+	output
+}
+
+/// Writes `diagnostics` for the file at `uri` as SARIF log into `output`.
+///
+/// `source` is the text the diagnostics' spans refer to.
+///
+/// # Errors
+///
+/// Iff writing to `output` fails.
+///
+/// # Panics
+///
+/// Iff a label's span is out of bounds or not on [`char`] boundaries in `source`.
+pub fn write(
+	output: &mut impl Write,
+	uri: &str,
+	source: &str,
+	diagnostics: &[Diagnostic<usize>],
+) -> fmt::Result {
+	let lines = LineIndex::new(source);
+
+	// Rules are identified by code, which some diagnostic types share.
+	let mut rules: Vec<DiagnosticType> = vec![];
+	for diagnostic in diagnostics {
+		if !rules.iter().any(|type_| type_.code() == diagnostic.code()) {
+			rules.push(diagnostic.type_);
+		}
+	}
+
+	output.write_str(concat!(
+		r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","runs":[{"#,
+		r#""tool":{"driver":{"name":"taml","version":""#,
+		env!("CARGO_PKG_VERSION"),
+		r#"","informationUri":"https://github.com/Tamschi/taml","rules":["#,
+	))?;
+	for (i, type_) in rules.iter().enumerate() {
+		if i > 0 {
+			output.write_char(',')?;
+		}
+		output.write_str("{\"id\":")?;
+		write_string(output, &type_.code())?;
+		output.write_str(",\"name\":")?;
+		write_string(output, &format!("{:?}", type_))?;
+		output.write_str(",\"shortDescription\":{\"text\":")?;
+		write_string(output, type_.title)?;
+		output.write_str("},\"defaultConfiguration\":{\"level\":")?;
+		write_string(output, level_name(type_.level))?;
+		output.write_str("}}")?;
+	}
+	output.write_str(r#"]}},"columnKind":"unicodeCodePoints","results":["#)?;
+
+	for (i, diagnostic) in diagnostics.iter().enumerate() {
+		if i > 0 {
+			output.write_char(',')?;
+		}
+		output.write_str("{\"ruleId\":")?;
+		write_string(output, &diagnostic.code())?;
+		write!(
+			output,
+			",\"ruleIndex\":{}",
+			rules
+				.iter()
+				.position(|type_| type_.code() == diagnostic.code())
+				.expect("All rules were collected above.")
+		)?;
+		output.write_str(",\"level\":")?;
+		write_string(output, level_name(diagnostic.level()))?;
+
+		let mut message = diagnostic.message().to_string();
+		for caption in diagnostic
+			.labels
+			.iter()
+			.filter(|label| label.span.is_none())
+			.filter_map(|label| label.caption.as_ref())
+		{
+			message.push('\n');
+			message.push_str(caption);
+		}
+		output.write_str(",\"message\":{\"text\":")?;
+		write_string(output, &message)?;
+		output.write_char('}')?;
+
+		for (name, priority) in [
+			("locations", DiagnosticLabelPriority::Primary),
+			("relatedLocations", DiagnosticLabelPriority::Auxiliary),
+		] {
+			write!(output, ",\"{}\":[", name)?;
+			for (i, label) in diagnostic
+				.labels
+				.iter()
+				.filter(|label| label.priority == priority && label.span.is_some())
+				.enumerate()
+			{
+				if i > 0 {
+					output.write_char(',')?;
+				}
+				write_location(output, uri, &lines, label)?;
+			}
+			output.write_char(']')?;
+		}
+		output.write_char('}')?;
+	}
+	output.write_str("]}]}")
+}
+
+/// SARIF has no separate help level and calls suppressed results' level `"none"`.
+fn level_name(level: DiagnosticLevel) -> &'static str {
+	match level {
+		DiagnosticLevel::Allow => "none",
+		DiagnosticLevel::Help | DiagnosticLevel::Note => "note",
+		DiagnosticLevel::Warning => "warning",
+		DiagnosticLevel::Error => "error",
+	}
+}
+
+fn write_location(
+	output: &mut impl Write,
+	uri: &str,
+	lines: &LineIndex,
+	label: &DiagnosticLabel<usize>,
+) -> fmt::Result {
+	let span = label
+		.span
+		.as_ref()
+		.expect("Only labels with span are written as location.");
+	let (start, end) = (lines.line_column(span.start), lines.line_column(span.end));
+	let (start_line, start_column) = (start.line + 1, start.char_column + 1);
+	let (end_line, end_column) = (end.line + 1, end.char_column + 1);
+
+	output.write_str("{\"physicalLocation\":{\"artifactLocation\":{\"uri\":")?;
+	write_string(output, uri)?;
+	write!(
+		output,
+		"}},\"region\":{{\"startLine\":{},\"startColumn\":{},\"endLine\":{},\"endColumn\":{}}}}}",
+		start_line, start_column, end_line, end_column,
+	)?;
+	if let Some(caption) = &label.caption {
+		output.write_str(",\"message\":{\"text\":")?;
+		write_string(output, caption)?;
+		output.write_char('}')?;
+	}
+	output.write_char('}')
+}
+
+#[cfg(test)]
+#[test]
+fn sarif() {
+	let source = "a: 1\na: 2\n";
+	let diagnostics = [
+		Diagnostic::new(
+			DiagnosticType::KeyPreviouslyDefined,
+			vec![
+				DiagnosticLabel::new("Duplicate.", 5..6, DiagnosticLabelPriority::Primary),
+				DiagnosticLabel::new("First.", 0..1, DiagnosticLabelPriority::Auxiliary),
+				DiagnosticLabel::new("Note.", None, DiagnosticLabelPriority::Auxiliary),
+			],
+		),
+		Diagnostic::new(DiagnosticType::KeyPreviouslyDefined, vec![]),
+	];
+	assert_eq!(
+		to_string("file:///config.taml", source, &diagnostics),
+		concat!(
+			r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","runs":[{"tool":{"driver":{"name":"taml","version":""#,
+			env!("CARGO_PKG_VERSION"),
+			r#"","informationUri":"https://github.com/Tamschi/taml","rules":[{"id":"TAML-P0013","name":"KeyPreviouslyDefined","shortDescription":{"text":"Key previously defined"},"defaultConfiguration":{"level":"error"}}]}},"columnKind":"unicodeCodePoints","results":["#,
+			r#"{"ruleId":"TAML-P0013","ruleIndex":0,"level":"error","message":{"text":"Key previously defined\nNote."},"#,
+			r#""locations":[{"physicalLocation":{"artifactLocation":{"uri":"file:///config.taml"},"region":{"startLine":2,"startColumn":1,"endLine":2,"endColumn":2}},"message":{"text":"Duplicate."}}],"#,
+			r#""relatedLocations":[{"physicalLocation":{"artifactLocation":{"uri":"file:///config.taml"},"region":{"startLine":1,"startColumn":1,"endLine":1,"endColumn":2}},"message":{"text":"First."}}]},"#,
+			r#"{"ruleId":"TAML-P0013","ruleIndex":0,"level":"error","message":{"text":"Key previously defined"},"locations":[],"relatedLocations":[]}]}]}"#,
+		)
+	);
+}
+
+#[cfg(test)]
+#[test]
+fn shared_code() {
+	let diagnostics = [
+		Diagnostic::new(DiagnosticType::UnclosedTabularPathSection, vec![]),
+		Diagnostic::new(DiagnosticType::DuplicateEnumInstantiation, vec![]),
+	];
+	let sarif = to_string("file:///config.taml", "", &diagnostics);
+	assert_eq!(sarif.matches(r#"{"id":"TAML-P0015""#).count(), 1);
+	assert_eq!(sarif.matches(r#""ruleIndex":0"#).count(), 2);
+}
@@ -0,0 +1,712 @@
+//! Format-preserving edits of TAML documents.
+//!
+//! A [`Document`] keeps its source text and only rewrites the lines affected by each change,
+//! so comments, blank lines, heading style and quoting elsewhere stay byte-identical.
+//!
+//! Values are selected by paths like `soundscapes.moments.layers` or `loops[1].volume`.
+//! Keys that aren't plain identifiers are quoted with backticks, as in TAML itself.
+//! Where a path continues past a list without index, it selects the list's last item,
+//! the same way headings do.
+
+use crate::{
+	cst::{Cst, Element, Entry, Line, Section},
+	diagnostics::DiagnosticLevel,
+	parsing::{parse_path_segment, BasicPathElementKey, IntoToken, Taml},
+	path::{parse_path, PathPart},
+	token::{escape_identifier, escape_string},
+	writing, Position, Token,
+};
+use logos::Logos as _;
+use smartstring::alias::String;
+use std::{
+	collections::HashMap,
+	fmt::{self, Display, Formatter},
+	iter,
+	ops::Range,
+};
+
+/// A TAML document that can be edited without disturbing its formatting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+	source: std::string::String,
+}
+
+/// Reasons why an edit could not be made.
+///
+/// The [`Document`] is left unchanged in each case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+	/// The path is malformed, or ends in a list index where a key is required.
+	InvalidPath,
+	/// Nothing exists at the path, and it can't be created by inserting a key-value pair or section.
+	NotFound,
+	/// The path selects a section or table, which can't be replaced by an inline value.
+	NotInline,
+	/// A value along the path is assigned inline, so nothing can be inserted below it.
+	NotAMap,
+	/// A row doesn't have as many values as the table has columns.
+	ArityMismatch { expected: usize, found: usize },
+	/// The edited document wouldn't be valid TAML, for example because the original already isn't.
+	Invalid,
+}
+
+/// A value in its TAML representation, as it appears on the right side of a key-value pair or in a table row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineValue(std::string::String);
+
+impl InlineValue {
+	/// Formats `value` as TAML decimal.
+	///
+	/// Returns [`None`] for infinities and NaN, which TAML can't represent.
+	#[must_use]
+	pub fn decimal(value: f64) -> Option<Self> {
+		value.is_finite().then(|| {
+			let mut text = value.to_string();
+			if !text.contains('.') {
+				text.push_str(".0")
+			}
+			Self(text)
+		})
+	}
+
+	/// Writes `taml` as inline value.
+	///
+	/// # Errors
+	///
+	/// Iff `taml` is or contains a map or structured variant.
+	pub fn from_taml<P: Position>(taml: &Taml<'_, P>) -> Result<Self, writing::Error<P>> {
+		let mut text = std::string::String::new();
+		writing::write_inline(&mut text, taml)?;
+		Ok(Self(text))
+	}
+
+	#[must_use]
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl Display for InlineValue {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+macro_rules! from_integers {
+	($($type:ty),*$(,)?) => {$(
+		impl From<$type> for InlineValue {
+			fn from(value: $type) -> Self {
+				Self(value.to_string())
+			}
+		}
+	)*};
+}
+from_integers!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Booleans are written as the unit variants `true` and `false`.
+impl From<bool> for InlineValue {
+	fn from(value: bool) -> Self {
+		Self(if value { "true" } else { "false" }.into())
+	}
+}
+
+impl From<&str> for InlineValue {
+	fn from(value: &str) -> Self {
+		Self(escape_string(value).to_string())
+	}
+}
+
+impl From<std::string::String> for InlineValue {
+	fn from(value: std::string::String) -> Self {
+		value.as_str().into()
+	}
+}
+
+impl Document {
+	#[must_use]
+	pub fn new(source: impl Into<std::string::String>) -> Self {
+		Self {
+			source: source.into(),
+		}
+	}
+
+	/// The current source text of this document.
+	#[must_use]
+	pub fn as_str(&self) -> &str {
+		&self.source
+	}
+
+	#[must_use]
+	pub fn into_string(self) -> std::string::String {
+		self.source
+	}
+
+	/// Creates a lossless syntax tree of the current source text.
+	#[must_use]
+	pub fn cst(&self) -> Cst<'_> {
+		Cst::new(&self.source)
+	}
+
+	/// Assigns `value` to the key at `path`.
+	///
+	/// An existing value is replaced in place.
+	/// Otherwise, a key-value pair is appended to the last section that selects the parent map,
+	/// or, if there is none, a new section with a path heading is appended to the document.
+	///
+	/// # Errors
+	///
+	/// See [`Error`].
+	pub fn set(&mut self, path: &str, value: impl Into<InlineValue>) -> Result<(), Error> {
+		let edits = plan_set(&self.source, path, value.into())?;
+		self.apply(edits)
+	}
+
+	/// Removes the value at `path`, including any sections and table rows that define it.
+	///
+	/// Comments directly above removed lines are removed too.
+	///
+	/// # Errors
+	///
+	/// See [`Error`].
+	pub fn remove(&mut self, path: &str) -> Result<(), Error> {
+		let edits = plan_remove(&self.source, path)?;
+		self.apply(edits)
+	}
+
+	/// Appends a row to the last table that defines the list at `path`.
+	///
+	/// # Errors
+	///
+	/// See [`Error`].
+	pub fn push_row<V: Into<InlineValue>>(
+		&mut self,
+		path: &str,
+		values: impl IntoIterator<Item = V>,
+	) -> Result<(), Error> {
+		let values = values.into_iter().map(Into::into).collect::<Vec<_>>();
+		let edits = plan_push_row(&self.source, path, &values)?;
+		self.apply(edits)
+	}
+
+	/// Applies `edits` only if the result parses without errors.
+	fn apply(&mut self, mut edits: Vec<Edit>) -> Result<(), Error> {
+		edits.sort_by_key(|(span, _)| span.start);
+		let mut source = self.source.clone();
+		for (span, replacement) in edits.into_iter().rev() {
+			source.replace_range(span, &replacement)
+		}
+
+		let mut diagnostics = vec![];
+		if crate::parse(Token::lexer(&source).spanned(), &mut diagnostics).is_err()
+			|| diagnostics
+				.iter()
+				.any(|diagnostic| matches!(diagnostic.level(), DiagnosticLevel::Error))
+		{
+			return Err(Error::Invalid);
+		}
+		self.source = source;
+		Ok(())
+	}
+}
+
+impl Display for Document {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.source)
+	}
+}
+
+type Edit = (Range<usize>, std::string::String);
+
+fn plan_set(source: &str, path: &str, value: InlineValue) -> Result<Vec<Edit>, Error> {
+	let cst = Cst::new(source);
+	let outline = Outline::new(&cst);
+	let path = outline.resolve(path)?;
+	let (key, parent) = match path.split_last() {
+		Some((PathPart::Key(key), parent)) => (key, parent),
+		_ => return Err(Error::InvalidPath),
+	};
+
+	let mut target = None;
+	for (section, section_outline) in outline.sections(&cst) {
+		if section_outline.path.starts_with(&path) {
+			return Err(Error::NotInline);
+		}
+		if section_outline.arity.is_some() {
+			continue;
+		}
+		for entry in &section.entries {
+			if let Some((name, colon, value_elements)) = key_value(entry) {
+				let entry_path = child_path(&section_outline.path, name);
+				if entry_path == path {
+					let span = match (value_elements.first(), value_elements.last()) {
+						(Some(first), Some(last)) => first.span.start..last.span.end,
+						_ => colon.span.end..colon.span.end,
+					};
+					let edit = (span, value.0);
+					return Ok(vec![edit]);
+				} else if path.starts_with(&entry_path) {
+					return Err(Error::NotAMap);
+				}
+			}
+		}
+		if section_outline.path == parent {
+			target = Some(section);
+		}
+	}
+
+	let line = format!("{}: {}\n", escape_identifier(key), value);
+	let edit = if let Some(section) = target {
+		insert_into(&cst, section, &line)
+	} else if outline
+		.variants
+		.iter()
+		.any(|variant| parent.starts_with(variant))
+	{
+		// A heading can't select into an enum variant, only the variant's own section can add fields.
+		return Err(Error::NotAMap);
+	} else if parent.iter().all(|part| matches!(part, PathPart::Key(_))) {
+		let heading = parent
+			.iter()
+			.map(|part| match part {
+				PathPart::Key(key) => escape_identifier(key).to_string(),
+				PathPart::Index(_) => unreachable!(),
+			})
+			.collect::<Vec<_>>()
+			.join(".");
+		let separator = if source.is_empty() {
+			""
+		} else if source.ends_with('\n') {
+			"\n"
+		} else {
+			"\n\n"
+		};
+		(
+			source.len()..source.len(),
+			format!("{}# {}\n{}", separator, heading, line),
+		)
+	} else {
+		return Err(Error::NotFound);
+	};
+	Ok(vec![edit])
+}
+
+fn plan_remove(source: &str, path: &str) -> Result<Vec<Edit>, Error> {
+	let cst = Cst::new(source);
+	let outline = Outline::new(&cst);
+	let path = outline.resolve(path)?;
+
+	let mut edits = vec![];
+	for (section, section_outline) in outline.sections(&cst) {
+		if let Some(heading) = &section.heading {
+			if section_outline.path.starts_with(&path) {
+				let start = heading.attached_comments().next().map_or_else(
+					|| line_start(&heading.line),
+					|comment| element_line_start(comment),
+				);
+				// Blank lines directly above go too, so that the spacing around the section doesn't double up.
+				let before = source[..start].trim_end();
+				let start = if before.is_empty() {
+					0
+				} else {
+					before.len() + source[before.len()..].find('\n').expect("unreachable") + 1
+				};
+				let last = section.entries.last().unwrap_or(heading);
+				edits.push((start..line_end(&last.line), std::string::String::new()));
+				continue;
+			}
+		}
+
+		for (i, entry) in section.entries.iter().enumerate() {
+			let entry_path = match section_outline.arity {
+				Some(_) => {
+					let mut row_path = section_outline.path.clone();
+					row_path.push(PathPart::Index(section_outline.first_row + i));
+					row_path
+				}
+				None => match key_value(entry) {
+					Some((name, ..)) => child_path(&section_outline.path, name),
+					None => continue,
+				},
+			};
+			if entry_path.starts_with(&path) {
+				let start = entry.attached_comments().next().map_or_else(
+					|| line_start(&entry.line),
+					|comment| element_line_start(comment),
+				);
+				edits.push((start..line_end(&entry.line), std::string::String::new()));
+			}
+		}
+	}
+
+	if edits.is_empty() {
+		return Err(Error::NotFound);
+	}
+	Ok(edits)
+}
+
+fn plan_push_row(source: &str, path: &str, values: &[InlineValue]) -> Result<Vec<Edit>, Error> {
+	let cst = Cst::new(source);
+	let outline = Outline::new(&cst);
+	let path = outline.resolve(path)?;
+
+	let (section, arity) = outline
+		.sections(&cst)
+		.filter(|(_, section_outline)| section_outline.path == path)
+		.filter_map(|(section, section_outline)| Some((section, section_outline.arity?)))
+		.last()
+		.ok_or(Error::NotFound)?;
+	if values.len() != arity {
+		return Err(Error::ArityMismatch {
+			expected: arity,
+			found: values.len(),
+		});
+	}
+
+	let row = values
+		.iter()
+		.map(InlineValue::as_str)
+		.collect::<Vec<_>>()
+		.join(", ");
+	let edit = insert_into(&cst, section, &(row + "\n"));
+	Ok(vec![edit])
+}
+
+/// Concrete paths of the sections in a [`Cst`], as the parser would select them.
+struct Outline {
+	sections: Vec<Option<SectionOutline>>,
+	/// The lengths of all lists defined by headings, tables and inline values.
+	lists: HashMap<Vec<PathPart>, usize>,
+	/// The paths of enum variants instantiated by headings.
+	variants: Vec<Vec<PathPart>>,
+}
+
+struct SectionOutline {
+	/// The selected map, or the list that rows are added to.
+	path: Vec<PathPart>,
+	/// The number of columns, for tabular sections.
+	arity: Option<usize>,
+	/// The list index of the first row, for tabular sections.
+	first_row: usize,
+}
+
+impl Outline {
+	fn new(cst: &Cst<'_>) -> Self {
+		let mut sections = vec![];
+		let mut lists = HashMap::new();
+		let mut variants = vec![];
+		let mut parents: Vec<Option<Vec<PathPart>>> = vec![];
+		for section in &cst.sections {
+			let outline = match &section.heading {
+				None => Some(SectionOutline {
+					path: vec![],
+					arity: None,
+					first_row: 0,
+				}),
+				Some(heading) => {
+					let depth = section.depth();
+					parents.truncate(depth - 1);
+					let parent = if depth == 1 {
+						Some(vec![])
+					} else if parents.len() == depth - 1 {
+						parents.last().cloned().flatten()
+					} else {
+						None
+					};
+					let outline = parent.and_then(|parent| {
+						heading_outline(
+							parent,
+							heading,
+							section.entries.len(),
+							&mut lists,
+							&mut variants,
+						)
+					});
+					parents.resize(depth - 1, None);
+					parents.push(
+						outline
+							.as_ref()
+							.filter(|outline| outline.arity.is_none())
+							.map(|outline| outline.path.clone()),
+					);
+					outline
+				}
+			};
+
+			if let Some(outline) = outline.as_ref().filter(|outline| outline.arity.is_none()) {
+				for entry in &section.entries {
+					if let Some((name, _, value)) = key_value(entry) {
+						if let Some(len) = inline_list_len(value) {
+							lists.insert(child_path(&outline.path, name), len);
+						}
+					}
+				}
+			}
+			sections.push(outline);
+		}
+		Self {
+			sections,
+			lists,
+			variants,
+		}
+	}
+
+	fn sections<'a, 'b>(
+		&'a self,
+		cst: &'a Cst<'b>,
+	) -> impl Iterator<Item = (&'a Section<'b>, &'a SectionOutline)> {
+		cst.sections
+			.iter()
+			.zip(&self.sections)
+			.filter_map(|(section, outline)| Some((section, outline.as_ref()?)))
+	}
+
+	/// Parses `path` and selects the last item of any list that it continues past without index.
+	fn resolve(&self, path: &str) -> Result<Vec<PathPart>, Error> {
+		let mut parts = parse_path(path)
+			.map_err(|()| Error::InvalidPath)?
+			.into_iter()
+			.peekable();
+		let mut resolved = vec![];
+		while let Some(part) = parts.next() {
+			resolved.push(part);
+			if let Some(PathPart::Key(_)) = parts.peek() {
+				if let Some(&len) = self.lists.get(&resolved) {
+					resolved.push(PathPart::Index(len.checked_sub(1).ok_or(Error::NotFound)?));
+				}
+			}
+		}
+		Ok(resolved)
+	}
+}
+
+fn heading_outline(
+	mut path: Vec<PathPart>,
+	heading: &Entry<'_>,
+	rows: usize,
+	lists: &mut HashMap<Vec<PathPart>, usize>,
+	variants: &mut Vec<Vec<PathPart>>,
+) -> Option<SectionOutline> {
+	let end = heading.span().end;
+	let mut tokens = heading.line.elements[1..]
+		.iter()
+		.map(|element| (element.token.clone(), element.span.clone()))
+		.chain(iter::once((Token::Newline, end..end)))
+		.map(IntoToken::into_token)
+		.peekable();
+	let segment = parse_path_segment(&mut tokens, &mut ()).ok()?;
+
+	for element in &segment.base {
+		match &element.key {
+			BasicPathElementKey::Plain(key) => path.push(PathPart::Key(String::from(&**key))),
+			BasicPathElementKey::List { key, .. } => {
+				path.push(PathPart::Key(String::from(&**key)));
+				let len = lists.entry(path.clone()).or_insert(0);
+				path.push(PathPart::Index(*len));
+				*len += 1;
+			}
+		}
+		if element.variant.is_some() {
+			variants.push(path.clone());
+		}
+	}
+
+	match segment.tabular {
+		None => Some(SectionOutline {
+			path,
+			arity: None,
+			first_row: 0,
+		}),
+		Some(tabular) => {
+			match &tabular.base.first()?.key {
+				BasicPathElementKey::List { key, .. } => {
+					path.push(PathPart::Key(String::from(&**key)))
+				}
+				BasicPathElementKey::Plain(_) => return None,
+			}
+			let len = lists.entry(path.clone()).or_insert(0);
+			let first_row = *len;
+			*len += rows;
+			Some(SectionOutline {
+				path,
+				arity: Some(tabular.arity()),
+				first_row,
+			})
+		}
+	}
+}
+
+/// Splits a key-value pair entry into its key, colon and value elements.
+fn key_value<'a, 'b>(
+	entry: &'a Entry<'b>,
+) -> Option<(&'a str, &'a Element<'b>, &'a [Element<'b>])> {
+	match &entry.line.elements[..] {
+		[Element {
+			token: Token::Identifier(name),
+			..
+		}, colon @ Element {
+			token: Token::Colon,
+			..
+		}, value @ ..] => Some((name, colon, value)),
+		_ => None,
+	}
+}
+
+fn inline_list_len(value: &[Element<'_>]) -> Option<usize> {
+	if value.first()?.token != Token::Paren {
+		return None;
+	}
+	let mut depth = 0_usize;
+	let mut commas = 0;
+	for element in value {
+		match element.token {
+			Token::Brac | Token::Paren => depth += 1,
+			Token::Ket | Token::Thesis => depth = depth.saturating_sub(1),
+			Token::Comma if depth == 1 => commas += 1,
+			_ => (),
+		}
+	}
+	Some(if value.len() <= 2 { 0 } else { commas + 1 })
+}
+
+fn child_path(parent: &[PathPart], key: &str) -> Vec<PathPart> {
+	let mut path = parent.to_vec();
+	path.push(PathPart::Key(key.into()));
+	path
+}
+
+/// Creates an edit that appends `line` after the last entry of `section`, matching its indentation.
+fn insert_into(
+	cst: &Cst<'_>,
+	section: &Section<'_>,
+	line: &str,
+) -> (Range<usize>, std::string::String) {
+	match (section.entries.last(), &section.heading) {
+		(Some(entry), _) => insert_after(entry, entry.line.elements[0].leading_whitespace, line),
+		(None, Some(heading)) => insert_after(heading, "", line),
+		// Insert above the first heading and its comments, keeping them apart.
+		(None, None) => {
+			if let Some(heading) = cst.sections.get(1).and_then(|next| next.heading.as_ref()) {
+				let start = heading.attached_comments().next().map_or_else(
+					|| line_start(&heading.line),
+					|comment| element_line_start(comment),
+				);
+				(start..start, format!("{}\n", line))
+			} else {
+				let end = cst.source.len();
+				let separator = if cst.source.is_empty() || cst.source.ends_with('\n') {
+					""
+				} else {
+					"\n"
+				};
+				(end..end, format!("{}{}", separator, line))
+			}
+		}
+	}
+}
+
+fn insert_after(
+	entry: &Entry<'_>,
+	indentation: &str,
+	line: &str,
+) -> (Range<usize>, std::string::String) {
+	let end = line_end(&entry.line);
+	let separator = if entry.line.newline.is_some() {
+		""
+	} else {
+		"\n"
+	};
+	(end..end, format!("{}{}{}", separator, indentation, line))
+}
+
+fn element_line_start(element: &Element<'_>) -> usize {
+	element.span.start - element.leading_whitespace.len()
+}
+
+/// The start of `line`, including its indentation.
+fn line_start(line: &Line<'_>) -> usize {
+	element_line_start(
+		line.all_elements()
+			.next()
+			.expect("Lines in a `Cst` are never empty."),
+	)
+}
+
+/// The end of `line`, including its line break.
+fn line_end(line: &Line<'_>) -> usize {
+	line.all_elements()
+		.last()
+		.expect("Lines in a `Cst` are never empty.")
+		.span
+		.end
+}
+
+#[cfg(test)]
+#[test]
+fn edits() {
+	let source = "// Document comment.\n\n# [soundscapes]\n\n## [[loops].{sound, volume}]\n\"$sewer/amb_drips\", 0.8\n\n// The moments.\n## moments\nsound:  \"$sewer/moments/*\" // postfix\nlayers: 1\n\n# [soundscapes]\nname: `Second`\n";
+	let mut document = Document::new(source);
+
+	document.set("soundscapes[0].moments.layers", 2).unwrap();
+	document.set("soundscapes.name", "Renamed").unwrap();
+	document
+		.set(
+			"soundscapes[0].moments.sound",
+			InlineValue::decimal(1.).unwrap(),
+		)
+		.unwrap();
+	document
+		.push_row(
+			"soundscapes[0].loops",
+			vec![
+				InlineValue::from("$sewer/amb_flies"),
+				InlineValue::decimal(0.1).unwrap(),
+			],
+		)
+		.unwrap();
+	assert_eq!(
+		document.as_str(),
+		"// Document comment.\n\n# [soundscapes]\n\n## [[loops].{sound, volume}]\n\"$sewer/amb_drips\", 0.8\n\"$sewer/amb_flies\", 0.1\n\n// The moments.\n## moments\nsound:  1.0 // postfix\nlayers: 2\n\n# [soundscapes]\nname: \"Renamed\"\n"
+	);
+
+	document.remove("soundscapes[0].moments").unwrap();
+	document.set("settings.sewer.`dripping?`", true).unwrap();
+	assert_eq!(
+		document.as_str(),
+		"// Document comment.\n\n# [soundscapes]\n\n## [[loops].{sound, volume}]\n\"$sewer/amb_drips\", 0.8\n\"$sewer/amb_flies\", 0.1\n\n# [soundscapes]\nname: \"Renamed\"\n\n# settings.sewer\n`dripping?`: true\n"
+	);
+
+	assert_eq!(document.set("soundscapes", 1), Err(Error::NotInline));
+	assert_eq!(
+		document.set("soundscapes.name.first", 1),
+		Err(Error::NotAMap)
+	);
+	assert_eq!(
+		document.push_row("soundscapes[0].loops", vec![1]),
+		Err(Error::ArityMismatch {
+			expected: 2,
+			found: 1
+		})
+	);
+	assert_eq!(document.remove("missing"), Err(Error::NotFound));
+
+	let mut detached =
+		Document::new("// Detached.\n\n// Attached.\n# removed\na: 1\n\n# kept\nb: 2\n");
+	detached.remove("removed").unwrap();
+	assert_eq!(detached.as_str(), "// Detached.\n\n# kept\nb: 2\n");
+	assert_eq!(document.set("a..b", 1), Err(Error::InvalidPath));
+
+	let mut variant = Document::new("# a:V\nb: 1\n");
+	variant.set("a.c", 2).unwrap();
+	assert_eq!(variant.as_str(), "# a:V\nb: 1\nc: 2\n");
+	assert_eq!(variant.set("a.x.y", 2), Err(Error::NotAMap));
+	assert_eq!(Document::new("a: ()\n").set("a.b", 1), Err(Error::NotFound));
+	assert_eq!(
+		Document::new("a: 1\nb: 1 # c\n").set("a", 2),
+		Err(Error::Invalid)
+	);
+
+	let map = document.cst().parse(&mut ()).unwrap();
+	assert!(
+		matches!(&map["soundscapes"].value, crate::parsing::TamlValue::List(list) if list.len() == 2)
+	);
+}
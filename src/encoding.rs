@@ -0,0 +1,598 @@
+//! Decoding of data literals (`<encoding:…>`).
+//!
+//! [`Encodings`] maps encoding names to [`Encoding`] implementations.
+//! Its [`Default`] contains the built-in encodings:
+//!
+//! | Name     | Example                           | Notes                                                   |
+//! |----------|-----------------------------------|---------------------------------------------------------|
+//! | `hex`    | `<hex:81 F0>`                     | Case-insensitive. Whitespace and `_` separate digits.   |
+//! | `bits`   | `<bits:1000_0001 1111_0000>`      | Most significant bit first. Must fill whole bytes.      |
+//! | `base64` | `<base64:gfA=>`                   | Standard alphabet. Padding is optional.                 |
+//! | `base32` | `<base32:QHYA====>`               | Standard alphabet, case-insensitive. Padding optional.  |
+//! | `utf8`   | `<utf8:text>`                     | The unescaped text as-is.                               |
+//! | `uuid`   | `<uuid:67e55044-10b1-426f-9247-bb680e5fe0c8>` | 16 bytes, with or without hyphens.          |
+//!
+//! Whitespace is ignored anywhere in `base64` and `base32` data.
+
+use crate::{
+	diagnostics::{Diagnostic, DiagnosticLabel, DiagnosticLabelPriority, DiagnosticType, Reporter},
+	token::escape_identifier,
+	DataLiteral, Position,
+};
+use std::{borrow::Cow, convert::TryInto, fmt::Write as _, ops::Range};
+
+/// A decoder for the unescaped contents of [`DataLiteral`]s.
+///
+/// Also implemented for matching closures.
+pub trait Encoding {
+	/// Decodes `unencoded_data` into bytes.
+	///
+	/// # Errors
+	///
+	/// Iff `unencoded_data` is not valid in this encoding.
+	fn decode(&self, unencoded_data: &str) -> Result<Vec<u8>, DecodeError>;
+}
+
+impl<F: Fn(&str) -> Result<Vec<u8>, DecodeError>> Encoding for F {
+	fn decode(&self, unencoded_data: &str) -> Result<Vec<u8>, DecodeError> {
+		self(unencoded_data)
+	}
+}
+
+/// Why and where decoding failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+	/// The invalid part of the unescaped data, as byte range.
+	pub range: Range<usize>,
+	/// Describes the problem.
+	pub message: Cow<'static, str>,
+}
+
+impl DecodeError {
+	/// Creates a new [`DecodeError`].
+	pub fn new(range: Range<usize>, message: impl Into<Cow<'static, str>>) -> Self {
+		Self {
+			range,
+			message: message.into(),
+		}
+	}
+}
+
+/// A registry of [`Encoding`]s by name.
+///
+/// The [`Default`] contains the built-in encodings listed in the [module documentation](`self`).
+pub struct Encodings {
+	encodings: Vec<(String, Box<dyn Encoding>)>,
+}
+
+impl Default for Encodings {
+	fn default() -> Self {
+		let mut encodings = Self::new();
+		encodings
+			.register("base32", Base32)
+			.register("base64", Base64)
+			.register("bits", Bits)
+			.register("hex", Hex)
+			.register("utf8", Utf8)
+			.register("uuid", Uuid);
+		encodings
+	}
+}
+
+impl Encodings {
+	/// Creates a new [`Encodings`] without any encodings.
+	#[must_use]
+	pub fn new() -> Self {
+		Self { encodings: vec![] }
+	}
+
+	/// Registers `encoding` as `name`, replacing any previous encoding with that name.
+	pub fn register(
+		&mut self,
+		name: impl Into<String>,
+		encoding: impl Encoding + 'static,
+	) -> &mut Self {
+		let name = name.into();
+		self.encodings.retain(|(existing, _)| *existing != name);
+		self.encodings.push((name, Box::new(encoding)));
+		self
+	}
+
+	/// Retrieves the encoding registered as `name`, if any.
+	#[must_use]
+	pub fn get(&self, name: &str) -> Option<&dyn Encoding> {
+		self.encodings
+			.iter()
+			.find(|(existing, _)| existing == name)
+			.map(|(_, encoding)| &**encoding)
+	}
+
+	/// Iterates over the names of the registered encodings, in registration order.
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.encodings.iter().map(|(name, _)| name.as_str())
+	}
+
+	/// Decodes `data_literal` into bytes.
+	///
+	/// Any errors are reported via `reporter`:
+	/// [`DiagnosticType::UnknownEncoding`] on the encoding and [`DiagnosticType::EncodeFailed`] on the invalid data.
+	///
+	/// # Errors
+	///
+	/// Iff the encoding is unknown or the data is invalid.
+	pub fn decode<P: Position>(
+		&self,
+		data_literal: &DataLiteral<'_, P>,
+		reporter: &mut impl Reporter<P>,
+	) -> Result<Vec<u8>, ()> {
+		if let Some(encoding) = self.get(&data_literal.encoding) {
+			encoding
+				.decode(&data_literal.unencoded_data)
+				.map_err(|error| {
+					let span = data_span(data_literal, error.range);
+					report_encode_failed(reporter, span, error.message);
+				})
+		} else {
+			reporter.report_with(|| self.unknown_encoding(data_literal));
+			Err(())
+		}
+	}
+
+	/// Decodes `data_literal` into exactly `N` bytes, like `<uuid:…>` into a `[u8; 16]`.
+	///
+	/// Any errors are reported via `reporter`, as with [`Encodings::decode`].
+	///
+	/// # Errors
+	///
+	/// Iff decoding fails or the result has a different length.
+	pub fn decode_array<P: Position, const N: usize>(
+		&self,
+		data_literal: &DataLiteral<'_, P>,
+		reporter: &mut impl Reporter<P>,
+	) -> Result<[u8; N], ()> {
+		let bytes = self.decode(data_literal, reporter)?;
+		let len = bytes.len();
+		bytes.try_into().map_err(|_| {
+			report_encode_failed(
+				reporter,
+				Some(data_literal.unencoded_data_span.clone()),
+				format!("Expected {} bytes, found {}.", N, len).into(),
+			);
+		})
+	}
+
+	/// Decodes `data_literal` into a [`String`], like `<utf8:…>`.
+	///
+	/// Any errors are reported via `reporter`, as with [`Encodings::decode`].
+	///
+	/// # Errors
+	///
+	/// Iff decoding fails or the result is not valid UTF-8.
+	pub fn decode_string<P: Position>(
+		&self,
+		data_literal: &DataLiteral<'_, P>,
+		reporter: &mut impl Reporter<P>,
+	) -> Result<String, ()> {
+		String::from_utf8(self.decode(data_literal, reporter)?).map_err(|error| {
+			report_encode_failed(
+				reporter,
+				Some(data_literal.unencoded_data_span.clone()),
+				format!("The decoded data is not valid UTF-8: {}", error).into(),
+			);
+		})
+	}
+
+	fn unknown_encoding<P: Position>(&self, data_literal: &DataLiteral<'_, P>) -> Diagnostic<P> {
+		let mut supported = String::new();
+		for (i, name) in self.names().enumerate() {
+			if i > 0 {
+				supported.push_str(", ");
+			}
+			write!(supported, "`{}`", escape_identifier(name)).expect("infallible");
+		}
+		Diagnostic::new(
+			DiagnosticType::UnknownEncoding,
+			vec![
+				DiagnosticLabel::new(
+					format!("Unknown encoding `{}`.", data_literal.encoding),
+					data_literal.encoding_span.clone(),
+					DiagnosticLabelPriority::Primary,
+				),
+				DiagnosticLabel::new(
+					if supported.is_empty() {
+						"No encodings are supported here.".to_string()
+					} else {
+						format!("Supported encodings: {}", supported)
+					},
+					None,
+					DiagnosticLabelPriority::Auxiliary,
+				),
+			],
+		)
+	}
+}
+
+fn report_encode_failed<P: Position>(
+	reporter: &mut impl Reporter<P>,
+	span: Option<Range<P>>,
+	message: Cow<'static, str>,
+) {
+	reporter.report_with(|| {
+		Diagnostic::new(
+			DiagnosticType::EncodeFailed,
+			vec![DiagnosticLabel::new(
+				message,
+				span,
+				DiagnosticLabelPriority::Primary,
+			)],
+		)
+	});
+}
+
+/// Converts `range` within the unescaped data of `data_literal` into a source span.
+///
+/// `\` and `>` are always escaped in the source. Carriage returns are escaped as `\r`
+/// only in literals with a quoted encoding, which is detected by the encoding's span being longer than its text.
+///
+/// Falls back to the whole [`DataLiteral::unencoded_data_span`] for positions without offsets.
+fn data_span<P: Position>(
+	data_literal: &DataLiteral<'_, P>,
+	range: Range<usize>,
+) -> Option<Range<P>> {
+	let escaped_cr = data_literal
+		.encoding_span
+		.start
+		.offset_range(0..data_literal.encoding.len())
+		.map_or(false, |unquoted| {
+			unquoted.end != data_literal.encoding_span.end
+		});
+	let escaped_offset = |offset: usize| -> usize {
+		data_literal.unencoded_data[..offset]
+			.chars()
+			.map(|c| match c {
+				'\\' | '>' => 2,
+				'\r' if escaped_cr => 2,
+				c => c.len_utf8(),
+			})
+			.sum()
+	};
+	data_literal
+		.unencoded_data_span
+		.start
+		.offset_range(escaped_offset(range.start)..escaped_offset(range.end))
+		.or_else(|| Some(data_literal.unencoded_data_span.clone()))
+}
+
+/// Decodes digits of `bits_per_digit` bits each, most significant first, skipping separators.
+///
+/// With `padding`, trailing `=` are accepted and fewer than `bits_per_digit` leftover bits are discarded.
+/// Otherwise, the digits must fill whole bytes.
+fn decode_digits(
+	unencoded_data: &str,
+	bits_per_digit: u32,
+	padding: bool,
+	is_separator: impl Fn(char) -> bool,
+	digit: impl Fn(char) -> Option<u32>,
+) -> Result<Vec<u8>, DecodeError> {
+	let mut bytes = vec![];
+	let mut buffer = 0_u32;
+	let mut buffered_bits = 0;
+	// Where the digits of the incomplete byte start, and the end of the last digit.
+	let mut partial_start = None;
+	let mut end = 0;
+	let mut padded = false;
+	for (i, c) in unencoded_data.char_indices() {
+		if is_separator(c) {
+			continue;
+		}
+		let range = i..i + c.len_utf8();
+		if padding && c == '=' {
+			padded = true;
+			continue;
+		}
+		let value = match digit(c).filter(|_| !padded) {
+			Some(value) => value,
+			None => {
+				return Err(DecodeError::new(
+					range,
+					if padded {
+						format!("Unexpected `{}` after padding.", c.escape_debug())
+					} else {
+						format!("Unexpected `{}`.", c.escape_debug())
+					},
+				))
+			}
+		};
+		partial_start.get_or_insert(range.start);
+		end = range.end;
+		buffer = buffer << bits_per_digit | value;
+		buffered_bits += bits_per_digit;
+		if buffered_bits >= 8 {
+			buffered_bits -= 8;
+			bytes.push((buffer >> buffered_bits).to_be_bytes()[3]);
+			buffer &= (1 << buffered_bits) - 1;
+			partial_start = (buffered_bits > 0).then(|| range.start);
+		}
+	}
+	match partial_start {
+		Some(start) if !padding || buffered_bits >= bits_per_digit => {
+			Err(DecodeError::new(start..end, "Incomplete byte."))
+		}
+		_ => Ok(bytes),
+	}
+}
+
+fn is_hex_separator(c: char) -> bool {
+	c.is_whitespace() || c == '_'
+}
+
+fn hex_digit(c: char) -> Option<u32> {
+	c.to_digit(16)
+}
+
+/// `<hex:81 F0>`: Pairs of case-insensitive hexadecimal digits, optionally separated by whitespace or `_`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Hex;
+
+impl Encoding for Hex {
+	fn decode(&self, unencoded_data: &str) -> Result<Vec<u8>, DecodeError> {
+		decode_digits(unencoded_data, 4, false, is_hex_separator, hex_digit)
+	}
+}
+
+/// `<bits:1000_0001 1111_0000>`: Binary digits, most significant first,
+/// optionally separated by whitespace or `_`. The digits must fill whole bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bits;
+
+impl Encoding for Bits {
+	fn decode(&self, unencoded_data: &str) -> Result<Vec<u8>, DecodeError> {
+		decode_digits(unencoded_data, 1, false, is_hex_separator, |c| {
+			c.to_digit(2)
+		})
+	}
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+	b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// `<base64:gfA=>`: Base64 with the standard alphabet (RFC 4648). Padding is optional and whitespace is ignored.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Base64;
+
+impl Base64 {
+	/// Encodes `bytes` as padded Base64 with the standard alphabet.
+	#[must_use]
+	pub fn encode(bytes: &[u8]) -> String {
+		let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+		for chunk in bytes.chunks(3) {
+			let bits = chunk.iter().enumerate().fold(0_u32, |bits, (i, &byte)| {
+				bits | u32::from(byte) << (16 - 8 * i)
+			});
+			for i in 0..4 {
+				if i <= chunk.len() {
+					encoded
+						.push(BASE64_ALPHABET[(bits >> (18 - 6 * i) & 0b11_1111) as usize].into());
+				} else {
+					encoded.push('=');
+				}
+			}
+		}
+		encoded
+	}
+}
+
+impl Encoding for Base64 {
+	fn decode(&self, unencoded_data: &str) -> Result<Vec<u8>, DecodeError> {
+		decode_digits(unencoded_data, 6, true, char::is_whitespace, |c| {
+			(0..)
+				.zip(BASE64_ALPHABET)
+				.find(|&(_, &digit)| char::from(digit) == c)
+				.map(|(value, _)| value)
+		})
+	}
+}
+
+/// `<base32:QHYA====>`: Base32 with the standard alphabet (RFC 4648), case-insensitive.
+/// Padding is optional and whitespace is ignored.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Base32;
+
+impl Encoding for Base32 {
+	fn decode(&self, unencoded_data: &str) -> Result<Vec<u8>, DecodeError> {
+		decode_digits(unencoded_data, 5, true, char::is_whitespace, |c| {
+			match c.to_ascii_uppercase() {
+				c @ 'A'..='Z' => Some(u32::from(c) - u32::from('A')),
+				c @ '2'..='7' => Some(u32::from(c) - u32::from('2') + 26),
+				_ => None,
+			}
+		})
+	}
+}
+
+/// `<utf8:text>`: The unescaped data as UTF-8 bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Utf8;
+
+impl Encoding for Utf8 {
+	fn decode(&self, unencoded_data: &str) -> Result<Vec<u8>, DecodeError> {
+		Ok(unencoded_data.as_bytes().to_vec())
+	}
+}
+
+/// `<uuid:67e55044-10b1-426f-9247-bb680e5fe0c8>`: 32 hexadecimal digits,
+/// either in the hyphenated 8-4-4-4-12 form or without hyphens.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Uuid;
+
+impl Encoding for Uuid {
+	fn decode(&self, unencoded_data: &str) -> Result<Vec<u8>, DecodeError> {
+		const HYPHENS: [usize; 4] = [8, 13, 18, 23];
+		let hyphenated = unencoded_data.len() == 36;
+		for (i, c) in unencoded_data.char_indices() {
+			let expect_hyphen = hyphenated && HYPHENS.contains(&i);
+			if (c == '-') != expect_hyphen || (c != '-' && hex_digit(c).is_none()) {
+				return Err(DecodeError::new(
+					i..i + c.len_utf8(),
+					if expect_hyphen {
+						format!("Expected `-`, found `{}`.", c.escape_debug())
+					} else {
+						format!("Expected hexadecimal digit, found `{}`.", c.escape_debug())
+					},
+				));
+			}
+		}
+		if !hyphenated && unencoded_data.len() != 32 {
+			return Err(DecodeError::new(
+				0..unencoded_data.len(),
+				"Expected 32 hexadecimal digits, optionally hyphenated as 8-4-4-4-12.",
+			));
+		}
+		decode_digits(unencoded_data, 4, false, |c| c == '-', hex_digit)
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn builtins() {
+	let encodings = Encodings::default();
+	let decode = |encoding: &str, data: &str| encodings.get(encoding).unwrap().decode(data);
+
+	assert_eq!(decode("hex", "81 F0"), Ok(vec![0x81, 0xF0]));
+	assert_eq!(decode("hex", "81_f0\n00"), Ok(vec![0x81, 0xF0, 0x00]));
+	assert_eq!(
+		decode("hex", "81 F"),
+		Err(DecodeError::new(3..4, "Incomplete byte."))
+	);
+	assert_eq!(
+		decode("hex", "8g"),
+		Err(DecodeError::new(1..2, "Unexpected `g`."))
+	);
+
+	assert_eq!(decode("bits", "1000_0001 1111_0000"), Ok(vec![0x81, 0xF0]));
+	assert_eq!(
+		decode("bits", "1000_0001 1111"),
+		Err(DecodeError::new(10..14, "Incomplete byte."))
+	);
+
+	for bytes in [&b""[..], b"f", b"fo", b"foo", b"foob", &[0x81, 0xF0, 0xFF]] {
+		assert_eq!(decode("base64", &Base64::encode(bytes)), Ok(bytes.to_vec()));
+		assert_eq!(
+			decode("base64", Base64::encode(bytes).trim_end_matches('=')),
+			Ok(bytes.to_vec())
+		);
+	}
+	assert_eq!(decode("base64", "gfA=\n"), Ok(vec![0x81, 0xF0]));
+	assert_eq!(
+		decode("base64", "Zm9vY"),
+		Err(DecodeError::new(4..5, "Incomplete byte."))
+	);
+	assert_eq!(
+		decode("base64", "gf=A"),
+		Err(DecodeError::new(3..4, "Unexpected `A` after padding."))
+	);
+
+	assert_eq!(decode("base32", "QHYA===="), Ok(vec![0x81, 0xF0]));
+	assert_eq!(decode("base32", "mzxw6ytb"), Ok(b"fooba".to_vec()));
+	assert_eq!(decode("base32", "MZXW6YQ"), Ok(b"foob".to_vec()));
+	assert_eq!(
+		decode("base32", "MZX"),
+		Err(DecodeError::new(1..3, "Incomplete byte."))
+	);
+	assert_eq!(
+		decode("base32", "MZ1"),
+		Err(DecodeError::new(2..3, "Unexpected `1`."))
+	);
+
+	assert_eq!(decode("utf8", "tëxt"), Ok("tëxt".as_bytes().to_vec()));
+
+	let uuid = vec![
+		0x67, 0xE5, 0x50, 0x44, 0x10, 0xB1, 0x42, 0x6F, 0x92, 0x47, 0xBB, 0x68, 0x0E, 0x5F, 0xE0,
+		0xC8,
+	];
+	assert_eq!(
+		decode("uuid", "67e55044-10b1-426f-9247-bb680e5fe0c8"),
+		Ok(uuid.clone())
+	);
+	assert_eq!(decode("uuid", "67e5504410b1426f9247bb680e5fe0c8"), Ok(uuid));
+	assert_eq!(
+		decode("uuid", "67e55044-10b1-426f-9247_bb680e5fe0c8"),
+		Err(DecodeError::new(23..24, "Expected `-`, found `_`."))
+	);
+	assert_eq!(
+		decode("uuid", "67e55044"),
+		Err(DecodeError::new(
+			0..8,
+			"Expected 32 hexadecimal digits, optionally hyphenated as 8-4-4-4-12."
+		))
+	);
+}
+
+#[cfg(test)]
+#[test]
+fn diagnostics() {
+	use crate::{parsing::TamlValue, Token};
+	use logos::Logos as _;
+
+	let encodings = Encodings::default();
+	let decode = |text: &str| {
+		let mut diagnostics = vec![];
+		let map = crate::parse(Token::lexer(text).spanned(), &mut diagnostics).unwrap();
+		let data_literal = match &map["a"].value {
+			TamlValue::DataLiteral(data_literal) => data_literal,
+			_ => panic!("Expected data literal."),
+		};
+		let result = encodings.decode_array::<_, 2>(data_literal, &mut diagnostics);
+		(
+			result,
+			diagnostics
+				.iter()
+				.map(|diagnostic| (diagnostic.type_, diagnostic.labels[0].span.clone()))
+				.collect::<Vec<_>>(),
+		)
+	};
+
+	assert_eq!(decode("a: <hex:81 F0>"), (Ok([0x81, 0xF0]), vec![]));
+	assert_eq!(
+		decode("a: <hex:81 F0 00>"),
+		(Err(()), vec![(DiagnosticType::EncodeFailed, Some(8..16))])
+	);
+	assert_eq!(
+		decode("a: <hex:81 FG>"),
+		(Err(()), vec![(DiagnosticType::EncodeFailed, Some(12..13))])
+	);
+	assert_eq!(
+		decode(r"a: <utf8:\>\\x>"),
+		(Err(()), vec![(DiagnosticType::EncodeFailed, Some(9..14))])
+	);
+	assert_eq!(
+		decode(r"a: <hex:\>\\x>"),
+		(Err(()), vec![(DiagnosticType::EncodeFailed, Some(8..10))])
+	);
+	assert_eq!(
+		decode(r"a: <`hex`:AB\rG>"),
+		(Err(()), vec![(DiagnosticType::EncodeFailed, Some(14..15))])
+	);
+	assert_eq!(
+		decode(r"a: <`he x`:\\\rx>"),
+		(
+			Err(()),
+			vec![(DiagnosticType::UnknownEncoding, Some(4..10))]
+		)
+	);
+
+	let mut custom = Encodings::new();
+	custom.register("he x", |data: &str| {
+		data.find('x')
+			.map_or(Ok(vec![]), |i| Err(DecodeError::new(i..i + 1, "No x.")))
+	});
+	let mut diagnostics = vec![];
+	let text = "a: <`he x`:\\\\\\rx>";
+	let map = crate::parse(Token::lexer(text).spanned(), &mut diagnostics).unwrap();
+	let data_literal = match &map["a"].value {
+		TamlValue::DataLiteral(data_literal) => data_literal,
+		_ => panic!("Expected data literal."),
+	};
+	assert_eq!(custom.decode(data_literal, &mut diagnostics), Err(()));
+	assert_eq!(diagnostics[0].labels[0].span, Some(15..16));
+}
@@ -1,26 +1,164 @@
-use crate::token::Token;
+//! Canonical formatting of TAML source text.
+//!
+//! [`format`] is the complete formatter.
+//! [`CanonicalFormatScanner`] exposes the underlying per-token decisions for callers that produce their own output.
+
+use crate::{
+	cst::{Cst, Element},
+	diagnostics::{Diagnostic, DiagnosticLevel},
+	token::Token,
+};
+use core::{cmp::Ordering, iter, ops::Range};
+use logos::Logos as _;
+use std::fmt::Write as _;
+
+/// A replacement of part of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+	/// The byte range in the original text that is replaced.
+	pub span: Range<usize>,
+	pub replacement: std::string::String,
+}
+
+/// Options for [`format_with`] and [`CanonicalFormatScanner::with_options`].
+///
+/// The [`Default`] matches [`format`].
+#[derive(Debug, Clone, Copy)]
+pub struct FormatOptions {
+	/// The number of blank lines before each heading, except at the start of the document.
+	pub blank_lines_before_headings: usize,
+	/// If set, the number of blank lines between each heading and the following line.
+	///
+	/// Otherwise, blank lines there are treated like anywhere else.
+	pub blank_lines_after_headings: Option<usize>,
+	/// The maximum number of consecutive blank lines that are kept.
+	///
+	/// Blank lines at the start and end of the document are always removed.
+	pub max_blank_lines: usize,
+	/// Whether comments are written with exactly one space after `//` (unless empty).
+	pub normalize_comment_space: bool,
+	/// Whether the output ends with a line break.
+	pub final_newline: FinalNewline,
+	/// The line break written after each line.
+	///
+	/// Line breaks inside quoted literals are part of their value and are never changed.
+	pub line_ending: LineEnding,
+	/// If set, key-value pairs are reordered within each block of consecutive key-value lines.
+	///
+	/// Blank lines, comment-only lines and headings end a block, so nothing is moved across them.
+	/// Postfix comments move along with their line.
+	pub sort_fields: Option<FieldOrder>,
+	/// How cells in tabular sections are spaced.
+	pub table_style: TableStyle,
+}
+
+impl Default for FormatOptions {
+	fn default() -> Self {
+		Self {
+			blank_lines_before_headings: 1,
+			blank_lines_after_headings: None,
+			max_blank_lines: 1,
+			normalize_comment_space: false,
+			final_newline: FinalNewline::Preserve,
+			line_ending: LineEnding::Lf,
+			sort_fields: None,
+			table_style: TableStyle::Compact,
+		}
+	}
+}
+
+/// Values of [`FormatOptions::final_newline`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinalNewline {
+	/// Keep a line break at the end of the document if there is one.
+	Preserve,
+	/// End non-empty documents with a line break.
+	Ensure,
+	/// Remove the line break at the end of the document.
+	Remove,
+}
+
+/// Values of [`FormatOptions::line_ending`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+	/// `\n`
+	Lf,
+	/// `\r\n`
+	CrLf,
+}
+
+impl LineEnding {
+	#[must_use]
+	pub fn as_str(self) -> &'static str {
+		match self {
+			LineEnding::Lf => "\n",
+			LineEnding::CrLf => "\r\n",
+		}
+	}
+}
+
+/// How [`FormatOptions::sort_fields`] orders keys.
+#[derive(Debug, Clone, Copy)]
+pub enum FieldOrder {
+	/// By the keys' Unicode code points.
+	Alphabetical,
+	/// By a caller-supplied comparison of (unescaped) keys.
+	///
+	/// The sort is stable, so keys that compare as equal keep their relative order.
+	Custom(fn(&str, &str) -> Ordering),
+}
+
+/// How [`FormatOptions::table_style`] spaces table cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableStyle {
+	/// A single space after each comma.
+	Compact,
+	/// Cells are padded so that commas line up column-wise within each tabular section,
+	/// assuming a monospace font.
+	Aligned,
+}
+
+impl FieldOrder {
+	fn compare(self, a: &str, b: &str) -> Ordering {
+		match self {
+			FieldOrder::Alphabetical => a.cmp(b),
+			FieldOrder::Custom(compare) => compare(a, b),
+		}
+	}
+}
 
 #[derive(Debug)]
 pub struct CanonicalFormatScanner {
 	state: State,
+	options: FormatOptions,
+	/// The number of line breaks since the last other token.
+	newlines: usize,
+	/// Whether the last line that contained tokens was a heading.
+	after_heading: bool,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Recommendation {
+	#[deprecated = "no longer recommended, as blank lines are now recommended before the token that follows them"]
 	Recommended,
 	Required,
 	PrependSpace,
 	PrependSpaceRequired,
+	#[deprecated = "replaced by `PrependNewlines(1)`"]
 	PrependNewline,
+	#[deprecated = "replaced by `PrependNewlines(2)`"]
 	PrependTwoNewlines,
+	/// Prepend this many line breaks to the token, which is at the start of a line,
+	/// so that as many blank lines precede it.
+	PrependNewlines(usize),
 	SkipToken,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 enum State {
-	MultiNewlineOrInitial,
+	Initial,
+	Newline,
 	Hashed,
-	SingleNewline,
 	Identifier,
 	Number,
 	Comment,
@@ -32,8 +170,17 @@ enum State {
 impl CanonicalFormatScanner {
 	#[must_use]
 	pub fn new() -> Self {
+		Self::with_options(&FormatOptions::default())
+	}
+
+	/// Creates a scanner that applies the blank line policy of `options`.
+	#[must_use]
+	pub fn with_options(options: &FormatOptions) -> Self {
 		Self {
-			state: State::MultiNewlineOrInitial,
+			state: State::Initial,
+			options: *options,
+			newlines: 0,
+			after_heading: false,
 		}
 	}
 }
@@ -58,13 +205,29 @@ impl CanonicalFormatScanner {
 				panic!("Invalid token sequence: Comments can only be followed by newlines")
 			}
 
-			(State::MultiNewlineOrInitial, Token::Newline) => Recommendation::SkipToken,
-			(State::SingleNewline, Token::Newline) => Recommendation::Recommended,
+			// Blank lines are only written once the next line is known.
+			(State::Initial | State::Newline, Token::Newline) => Recommendation::SkipToken,
 			(_, Token::Newline) => Recommendation::Required,
 
-			(State::MultiNewlineOrInitial, Token::HeadingHashes(_)) => Recommendation::Required,
-			(State::SingleNewline, Token::HeadingHashes(_)) => Recommendation::PrependNewline,
-			(_, Token::HeadingHashes(_)) => Recommendation::PrependTwoNewlines,
+			(State::Initial, _) => Recommendation::Required,
+			(State::Newline, _) => {
+				let blank_lines = (self.newlines - 1).min(self.options.max_blank_lines);
+				let blank_lines = match token {
+					Token::HeadingHashes(_) => self.options.blank_lines_before_headings,
+					_ if self.after_heading => self
+						.options
+						.blank_lines_after_headings
+						.unwrap_or(blank_lines),
+					_ => blank_lines,
+				};
+				if blank_lines == 0 {
+					Recommendation::Required
+				} else {
+					Recommendation::PrependNewlines(blank_lines)
+				}
+			}
+
+			(_, Token::HeadingHashes(_)) => Recommendation::PrependNewlines(2),
 			(State::Hashed, _) => Recommendation::PrependSpace,
 
 			(State::Identifier, Token::Identifier(_)) => Recommendation::PrependSpaceRequired,
@@ -77,9 +240,6 @@ impl CanonicalFormatScanner {
 				| Token::InvalidZeroPrefixedInteger(_),
 			) => Recommendation::PrependSpaceRequired,
 
-			(State::SingleNewline | State::MultiNewlineOrInitial, Token::Comment(_)) => {
-				Recommendation::Required
-			}
 			(_, Token::Comment(_)) => Recommendation::PrependSpace,
 
 			(State::ColonOrComma, _) => Recommendation::PrependSpace,
@@ -87,15 +247,22 @@ impl CanonicalFormatScanner {
 			(_, _) => Recommendation::Required,
 		};
 
+		if let Token::Newline = token {
+			self.newlines += 1;
+		} else {
+			if self.state == State::Newline {
+				self.after_heading = false;
+			}
+			self.newlines = 0;
+		}
+		if let Token::HeadingHashes(_) = token {
+			self.after_heading = true;
+		}
+
 		self.state = match token {
 			Token::HeadingHashes(_) => State::Hashed,
-			Token::Newline
-				if self.state == State::MultiNewlineOrInitial
-					|| self.state == State::SingleNewline =>
-			{
-				State::MultiNewlineOrInitial
-			}
-			Token::Newline => State::SingleNewline,
+			Token::Newline if self.state == State::Initial => State::Initial,
+			Token::Newline => State::Newline,
 			Token::Comment(_) => State::Comment,
 			Token::Decimal(_)
 			| Token::Integer(_)
@@ -126,4 +293,423 @@ impl CanonicalFormatScanner {
 	}
 }
 
-//TODO: Test stability
+/// Formats `source` canonically.
+///
+/// Comments are kept, runs of blank lines are collapsed to one,
+/// headings are separated from preceding content by a blank line
+/// and literals are normalised to how [`Token`]'s [`Display`](`core::fmt::Display`) writes them.
+///
+/// Formatting is idempotent: Formatting the output again does not change it.
+///
+/// # Errors
+///
+/// Iff `source` is not valid TAML, in which case the parser's diagnostics are returned.
+pub fn format(source: &str) -> Result<std::string::String, Vec<Diagnostic<usize>>> {
+	format_with(source, &FormatOptions::default())
+}
+
+/// Formats `source` canonically, according to `options`.
+///
+/// Formatting is idempotent for any given `options`.
+///
+/// # Errors
+///
+/// Iff `source` is not valid TAML, in which case the parser's diagnostics are returned.
+pub fn format_with(
+	source: &str,
+	options: &FormatOptions,
+) -> Result<std::string::String, Vec<Diagnostic<usize>>> {
+	let tokens = Token::lexer(source).spanned().collect::<Vec<_>>();
+
+	validate(&tokens)?;
+
+	let newline = options.line_ending.as_str();
+	let mut output = std::string::String::new();
+	let mut scanner = CanonicalFormatScanner::with_options(options);
+	for (token, _) in &tokens {
+		write_token(&mut output, token, scanner.next(token), options);
+	}
+
+	if let Some(order) = options.sort_fields {
+		output = sort_fields(&output, order);
+	}
+	if options.table_style == TableStyle::Aligned {
+		output = align_tables(&output);
+	}
+
+	match options.final_newline {
+		FinalNewline::Preserve => (),
+		FinalNewline::Ensure => {
+			if !output.is_empty() && !output.ends_with(newline) {
+				output.push_str(newline)
+			}
+		}
+		FinalNewline::Remove => {
+			if output.ends_with(newline) {
+				output.truncate(output.len() - newline.len())
+			}
+		}
+	}
+	Ok(output)
+}
+
+/// Parses `tokens` and returns the diagnostics if any of them is an error.
+fn validate(tokens: &[(Token<'_, usize>, Range<usize>)]) -> Result<(), Vec<Diagnostic<usize>>> {
+	let mut diagnostics = vec![];
+	let parsed = crate::parse(tokens.iter().cloned(), &mut diagnostics);
+	if parsed.is_err()
+		|| diagnostics
+			.iter()
+			.any(|diagnostic| matches!(diagnostic.level(), DiagnosticLevel::Error))
+	{
+		Err(diagnostics)
+	} else {
+		Ok(())
+	}
+}
+
+/// Writes `token` with the whitespace `recommendation` calls for.
+fn write_token(
+	output: &mut std::string::String,
+	token: &Token<'_, usize>,
+	recommendation: Recommendation,
+	options: &FormatOptions,
+) {
+	let newline = options.line_ending.as_str();
+	match recommendation {
+		#[allow(deprecated)]
+		Recommendation::Recommended | Recommendation::Required => (),
+		Recommendation::PrependSpace | Recommendation::PrependSpaceRequired => output.push(' '),
+		#[allow(deprecated)]
+		Recommendation::PrependNewline => output.push_str(newline),
+		#[allow(deprecated)]
+		Recommendation::PrependTwoNewlines => output.extend(iter::repeat(newline).take(2)),
+		Recommendation::PrependNewlines(count) => output.extend(iter::repeat(newline).take(count)),
+		Recommendation::SkipToken => return,
+	}
+	match token {
+		Token::Newline => output.push_str(newline),
+		Token::Comment(comment) if options.normalize_comment_space => {
+			let comment = comment.trim_start_matches([' ', '\t']);
+			output.push_str("//");
+			if !comment.is_empty() {
+				output.push(' ');
+				output.push_str(comment);
+			}
+		}
+		_ => write!(output, "{}", token).expect("infallible"),
+	}
+}
+
+/// Formats the lines of `source` that `range` touches, according to `options`.
+///
+/// The scanner rules, comment spacing and line endings are applied as by [`format_with`],
+/// in the context of the whole document.
+/// [`FormatOptions::sort_fields`] and [`TableStyle::Aligned`] depend on lines outside the range and are ignored.
+///
+/// Text outside these lines is only changed to fix up blank lines directly before them
+/// and, if they include the last line of the document, the end of the document.
+///
+/// The returned edits are in document order, don't overlap and each change as little text as possible.
+///
+/// # Errors
+///
+/// Iff `source` is not valid TAML, in which case the parser's diagnostics are returned.
+pub fn format_range(
+	source: &str,
+	range: Range<usize>,
+	options: &FormatOptions,
+) -> Result<Vec<Edit>, Vec<Diagnostic<usize>>> {
+	let tokens = Token::lexer(source).spanned().collect::<Vec<_>>();
+
+	validate(&tokens)?;
+
+	// The token index ranges of each line, including its line break.
+	let mut lines = vec![];
+	let mut line_start = 0;
+	for (i, (token, _)) in tokens.iter().enumerate() {
+		if *token == Token::Newline {
+			lines.push(line_start..i + 1);
+			line_start = i + 1;
+		}
+	}
+	if line_start < tokens.len() {
+		lines.push(line_start..tokens.len());
+	}
+	let line_end = |line: &Range<usize>| tokens[line.end - 1].1.end;
+	let is_empty = |line: &Range<usize>| line.len() == 1 && tokens[line.start].0 == Token::Newline;
+
+	// Blank lines at either boundary belong to the token after them, so the selection starts and ends with content.
+	// An empty range selects the line it's on.
+	let range_end = range.end.max(range.start + 1);
+	let mut selected = lines.iter().enumerate().filter(|(i, line)| {
+		let start = i
+			.checked_sub(1)
+			.map_or(0, |previous| line_end(&lines[previous]));
+		start < range_end && range.start < line_end(line) && !is_empty(line)
+	});
+	let (first, last) = match (selected.next(), selected.next_back()) {
+		(Some((first, _)), Some((last, _))) => (first, last),
+		(Some((first, _)), None) => (first, first),
+		// Documents without content format to nothing.
+		(None, _) if lines.iter().all(is_empty) => {
+			return Ok(minimize(
+				source,
+				Edit {
+					span: 0..source.len(),
+					replacement: std::string::String::new(),
+				},
+			)
+			.into_iter()
+			.collect())
+		}
+		(None, _) => return Ok(vec![]),
+	};
+	let previous_content = lines[..first].iter().rposition(|line| !is_empty(line));
+	let start = previous_content.map_or(0, |previous| line_end(&lines[previous]));
+	let at_end = lines[last + 1..].iter().all(is_empty);
+	let tokens_range = lines[first].start..if at_end {
+		tokens.len()
+	} else {
+		lines[last].end
+	};
+
+	let mut scanner = CanonicalFormatScanner::with_options(options);
+	for (token, _) in &tokens[..tokens_range.start] {
+		scanner.next(token);
+	}
+
+	let mut edits = vec![];
+	let mut end = start;
+	let mut last_written = None;
+	for (token, span) in &tokens[tokens_range] {
+		let mut replacement = std::string::String::new();
+		write_token(&mut replacement, token, scanner.next(token), options);
+		if !replacement.is_empty() {
+			last_written = Some(edits.len());
+		}
+		edits.push(Edit {
+			span: end..span.end,
+			replacement,
+		});
+		end = span.end;
+	}
+
+	if at_end {
+		edits.push(Edit {
+			span: end..source.len(),
+			replacement: std::string::String::new(),
+		});
+		let newline = options.line_ending.as_str();
+		if let Some(last_written) = last_written {
+			let replacement = &mut edits[last_written].replacement;
+			match options.final_newline {
+				FinalNewline::Preserve => (),
+				FinalNewline::Ensure => {
+					if !replacement.ends_with(newline) {
+						replacement.push_str(newline)
+					}
+				}
+				FinalNewline::Remove => {
+					if replacement.ends_with(newline) {
+						replacement.truncate(replacement.len() - newline.len())
+					}
+				}
+			}
+		}
+	}
+
+	Ok(edits
+		.into_iter()
+		.filter_map(|edit| minimize(source, edit))
+		.collect())
+}
+
+/// Shrinks `edit` to exclude any unchanged text at its start and end, or discards it if it changes nothing.
+fn minimize(source: &str, mut edit: Edit) -> Option<Edit> {
+	let original = &source[edit.span.clone()];
+	let prefix = original
+		.char_indices()
+		.zip(edit.replacement.chars())
+		.find(|((_, a), b)| a != b)
+		.map_or_else(
+			|| original.len().min(edit.replacement.len()),
+			|((i, _), _)| i,
+		);
+	let suffix = original[prefix..]
+		.chars()
+		.rev()
+		.zip(edit.replacement[prefix..].chars().rev())
+		.take_while(|(a, b)| a == b)
+		.map(|(a, _)| a.len_utf8())
+		.sum::<usize>();
+
+	if prefix + suffix == original.len() && prefix + suffix == edit.replacement.len() {
+		return None;
+	}
+	edit.replacement = edit.replacement[prefix..edit.replacement.len() - suffix].to_owned();
+	edit.span = edit.span.start + prefix..edit.span.end - suffix;
+	Some(edit)
+}
+
+fn sort_fields(source: &str, order: FieldOrder) -> std::string::String {
+	fn flush(
+		output: &mut std::string::String,
+		block: &mut Vec<(&str, &str, &str)>,
+		order: FieldOrder,
+	) {
+		let mut sorted = block.clone();
+		sorted.sort_by(|(a, ..), (b, ..)| order.compare(a, b));
+		// Line breaks stay in place, in case the last line of the document doesn't have one.
+		for ((_, content, _), (_, _, newline)) in sorted.into_iter().zip(block.drain(..)) {
+			output.push_str(content);
+			output.push_str(newline);
+		}
+	}
+
+	let cst = Cst::new(source);
+	let mut output = std::string::String::with_capacity(source.len());
+	let mut block = vec![];
+	for line in cst.lines() {
+		let mut elements = line.all_elements();
+		let first = elements.next().expect("Lines in a `Cst` are never empty.");
+		let start = first.span.start - first.leading_whitespace.len();
+		let end = elements.last().unwrap_or(first).span.end;
+		let content_end = line
+			.newline
+			.as_ref()
+			.map_or(end, |newline| newline.span.start);
+
+		if let [Element {
+			token: Token::Identifier(key),
+			..
+		}, Element {
+			token: Token::Colon,
+			..
+		}, ..] = &line.elements[..]
+		{
+			block.push((
+				key.as_ref(),
+				&source[start..content_end],
+				&source[content_end..end],
+			));
+		} else {
+			flush(&mut output, &mut block, order);
+			output.push_str(&source[start..end]);
+		}
+	}
+	flush(&mut output, &mut block, order);
+	output.push_str(cst.trailing_whitespace);
+	output
+}
+
+fn align_tables(source: &str) -> std::string::String {
+	fn width(cell: &str) -> usize {
+		// Only the last line of multi-line strings matters for the following comma.
+		cell.rsplit('\n').next().unwrap_or(cell).chars().count()
+	}
+
+	let cst = Cst::new(source);
+	let mut edits = vec![];
+	for section in cst.sections.iter().filter(|section| section.is_tabular()) {
+		let rows = section
+			.entries
+			.iter()
+			.map(|entry| cells(&entry.line.elements))
+			.collect::<Vec<_>>();
+
+		let mut widths = vec![];
+		for row in &rows {
+			widths.resize(widths.len().max(row.len()), 0);
+			for (cell, column) in row.iter().zip(&mut widths) {
+				*column = (*column).max(width(&source[cell.clone()]));
+			}
+		}
+
+		for row in rows {
+			for (i, pair) in row.windows(2).enumerate() {
+				let padding = widths[i] - width(&source[pair[0].clone()]);
+				edits.push((
+					pair[0].end..pair[1].start,
+					format!("{}, ", " ".repeat(padding)),
+				));
+			}
+		}
+	}
+
+	let mut output = source.to_owned();
+	for (span, replacement) in edits.into_iter().rev() {
+		output.replace_range(span, &replacement)
+	}
+	output
+}
+
+/// The spans of the comma-separated values in a table row.
+fn cells(elements: &[Element<'_>]) -> Vec<Range<usize>> {
+	let mut cells = vec![];
+	let mut depth = 0_usize;
+	let mut start = None;
+	let mut end = 0;
+	for element in elements {
+		match element.token {
+			Token::Comma if depth == 0 => {
+				cells.extend(start.take().map(|start| start..end));
+				continue;
+			}
+			Token::Brac | Token::Bra | Token::Paren => depth += 1,
+			Token::Ket | Token::Ce | Token::Thesis => depth = depth.saturating_sub(1),
+			_ => (),
+		}
+		start.get_or_insert(element.span.start);
+		end = element.span.end;
+	}
+	cells.extend(start.map(|start| start..end));
+	cells
+}
+
+/// Finds literals and identifiers in `source` that aren't written canonically.
+///
+/// This rewrites unnecessarily quoted identifiers like `` `plain_name` `` to `plain_name`,
+/// decimals like `0.1000` to `0.1` and unnecessary escapes in strings and data literals,
+/// without changing anything else.
+/// Unrecognised input is skipped, so this also works on documents that are (currently) invalid.
+///
+/// The returned edits are in document order and don't overlap.
+#[must_use]
+pub fn normalize_literals(source: &str) -> Vec<Edit> {
+	Token::lexer(source)
+		.spanned()
+		.filter(|(token, _)| {
+			matches!(
+				token,
+				Token::String(_) | Token::DataLiteral(_) | Token::Decimal(_) | Token::Identifier(_)
+			)
+		})
+		.filter_map(|(token, span)| {
+			let replacement = token.to_string();
+			(source[span.clone()] != replacement).then(|| Edit { span, replacement })
+		})
+		.collect()
+}
+
+/// Applies `edits` to `source`.
+///
+/// # Panics
+///
+/// Iff any of the edits overlap or are out of bounds.
+#[must_use]
+pub fn apply_edits(source: &str, edits: &[Edit]) -> std::string::String {
+	let mut edits = edits.iter().collect::<Vec<_>>();
+	edits.sort_by_key(|edit| edit.span.start);
+
+	let mut output = std::string::String::with_capacity(source.len());
+	let mut end = 0;
+	for edit in edits {
+		assert!(edit.span.start >= end, "Overlapping edits.");
+		output.push_str(&source[end..edit.span.start]);
+		output.push_str(&edit.replacement);
+		end = edit.span.end;
+	}
+	output.push_str(&source[end..]);
+	output
+}
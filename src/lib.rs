@@ -20,12 +20,30 @@ use smartstring::alias::String;
 #[doc = include_str!("../README.md")]
 mod readme {}
 
+pub mod access;
+pub mod cst;
+#[cfg(feature = "serde")]
+pub mod de;
 pub mod diagnostics;
+pub mod editing;
+pub mod encoding;
 pub mod formatting;
+mod line_index;
 pub mod parsing;
+mod path;
+#[cfg(feature = "serde")]
+pub mod ser;
+mod source_map;
 mod token;
+pub mod writing;
 
+#[cfg(feature = "serde")]
+pub use de::{from_map, from_str, from_taml};
+pub use line_index::{LineColumn, LineIndex};
 pub use parsing::parse;
+#[cfg(feature = "serde")]
+pub use ser::{to_string, to_string_with};
+pub use source_map::{FileId, FilePosition, SourceMap};
 pub use token::Token;
 
 /// Shared variant payload data structure for data literals (`<…:…>`).
@@ -37,8 +55,22 @@ pub struct DataLiteral<'a, Position> {
 	pub unencoded_data_span: Range<Position>,
 }
 
+impl<'a, Position> DataLiteral<'a, Position> {
+	/// Converts [`Self::encoding_span`] and [`Self::unencoded_data_span`] using `f`.
+	pub fn map_position<P>(self, mut f: impl FnMut(Position) -> P) -> DataLiteral<'a, P> {
+		DataLiteral {
+			encoding: self.encoding,
+			encoding_span: f(self.encoding_span.start)..f(self.encoding_span.end),
+			unencoded_data: self.unencoded_data,
+			unencoded_data_span: f(self.unencoded_data_span.start)..f(self.unencoded_data_span.end),
+		}
+	}
+}
+
 /// Implemented by types usable as `Position` generic type parameter in this library.
-pub trait Position: Debug + Clone + Default + PartialEq {
+///
+/// Positions must be ordered by where they appear in the source, which is used to find the line a diagnostic refers to.
+pub trait Position: Debug + Clone + Default + PartialOrd {
 	/// Adds `self` to both limits of `local_range` and returns the result in [`Some`].  
 	/// If this operation does not make sense, [`None`] is returned instead.
 	fn offset_range(&self, local_range: Range<usize>) -> Option<Range<Self>>;
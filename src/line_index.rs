@@ -0,0 +1,175 @@
+use crate::{Position, Token};
+use logos::Logos as _;
+use std::ops::Range;
+
+/// A [`Position`] in terms of lines and columns, as used by editors.
+///
+/// All fields are 0-based. Columns are counted from the start of the line, in each of the units commonly used by tooling.
+/// A [`LineIndex`] converts byte offsets into this representation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineColumn {
+	/// The byte offset into the source.
+	pub offset: usize,
+	/// The line, where lines are separated by `'\n'`.
+	pub line: usize,
+	/// The column in UTF-8 bytes.
+	pub utf8_column: usize,
+	/// The column in UTF-16 code units, as used by the Language Server Protocol by default.
+	pub utf16_column: usize,
+	/// The column in [`char`]s, i.e. Unicode scalar values.
+	pub char_column: usize,
+}
+
+/// Columns can't be calculated without the source text,
+/// so [`offset_range`](`Position::offset_range`) always returns [`None`].
+///
+/// Use [`LineIndex`] to create [`LineColumn`]s instead.
+impl Position for LineColumn {
+	fn offset_range(&self, _local_range: Range<usize>) -> Option<Range<Self>> {
+		None
+	}
+}
+
+/// Converts byte offsets into a given source text into [`LineColumn`]s.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+	source: &'a str,
+	line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+	/// Indexes the line starts in `source`.
+	#[must_use]
+	pub fn new(source: &'a str) -> Self {
+		Self {
+			source,
+			line_starts: std::iter::once(0)
+				.chain(source.match_indices('\n').map(|(i, _)| i + 1))
+				.collect(),
+		}
+	}
+
+	/// The indexed source text.
+	#[must_use]
+	pub fn source(&self) -> &'a str {
+		self.source
+	}
+
+	/// The number of lines in the source, which is always at least 1.
+	#[must_use]
+	pub fn line_count(&self) -> usize {
+		self.line_starts.len()
+	}
+
+	/// The 0-based line that contains the byte `offset`.
+	///
+	/// Offsets past the end of the source are considered part of the last line.
+	#[must_use]
+	pub fn line(&self, offset: usize) -> usize {
+		self.line_starts.partition_point(|&start| start <= offset) - 1
+	}
+
+	/// The byte offset at which `line` starts, or [`None`] if there is no such line.
+	#[must_use]
+	pub fn line_start(&self, line: usize) -> Option<usize> {
+		self.line_starts.get(line).copied()
+	}
+
+	/// The text of `line` without its line break (`"\n"` or `"\r\n"`), or [`None`] if there is no such line.
+	#[must_use]
+	pub fn line_text(&self, line: usize) -> Option<&'a str> {
+		let start = self.line_start(line)?;
+		let end = self
+			.line_starts
+			.get(line + 1)
+			.map_or(self.source.len(), |next| next - 1);
+		Some(self.source[start..end].trim_end_matches('\r'))
+	}
+
+	/// Converts the byte `offset` into a [`LineColumn`].
+	///
+	/// # Panics
+	///
+	/// Iff `offset` is out of bounds or not on a [`char`] boundary.
+	#[must_use]
+	pub fn line_column(&self, offset: usize) -> LineColumn {
+		let line = self.line(offset);
+		let before = &self.source[self.line_starts[line]..offset];
+		LineColumn {
+			offset,
+			line,
+			utf8_column: before.len(),
+			utf16_column: before.encode_utf16().count(),
+			char_column: before.chars().count(),
+		}
+	}
+
+	/// Converts both limits of `range` into [`LineColumn`]s.
+	///
+	/// # Panics
+	///
+	/// Iff either limit is out of bounds or not on a [`char`] boundary.
+	#[must_use]
+	pub fn line_column_range(&self, range: Range<usize>) -> Range<LineColumn> {
+		self.line_column(range.start)..self.line_column(range.end)
+	}
+
+	/// Lexes the source with [`LineColumn`] positions, for use with [`parse`](`crate::parse`).
+	pub fn lex(&self) -> impl Iterator<Item = (Token<'a, LineColumn>, Range<LineColumn>)> + '_ {
+		Token::lexer(self.source)
+			.spanned()
+			.map(move |(token, span)| {
+				(
+					token.map_position(|offset| self.line_column(offset)),
+					self.line_column_range(span),
+				)
+			})
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn line_columns() {
+	use crate::{parse, parsing::TamlValue};
+
+	let source = "a: 1\r\n# `😀`\n`ä😀`: <hex:0f>\n";
+	let index = LineIndex::new(source);
+	assert_eq!(index.line_count(), 4);
+	assert_eq!(index.line_text(0), Some("a: 1"));
+	assert_eq!(index.line_text(3), Some(""));
+	assert_eq!(index.line_text(4), None);
+
+	let map = parse(index.lex(), &mut ()).unwrap();
+	let (key, value) = match &map["😀"].value {
+		TamlValue::Map(map) => map.first().unwrap(),
+		_ => panic!("Expected map."),
+	};
+	assert_eq!(
+		key.span.start,
+		LineColumn {
+			offset: 15,
+			line: 2,
+			utf8_column: 0,
+			utf16_column: 0,
+			char_column: 0,
+		}
+	);
+	assert_eq!(
+		key.span.end,
+		LineColumn {
+			offset: 23,
+			line: 2,
+			utf8_column: 8,
+			utf16_column: 5,
+			char_column: 4,
+		}
+	);
+	match &value.value {
+		TamlValue::DataLiteral(data_literal) => {
+			assert_eq!(data_literal.unencoded_data_span.start.char_column, 11);
+			assert_eq!(data_literal.unencoded_data_span.start.utf8_column, 15);
+		}
+		_ => panic!("Expected data literal."),
+	}
+	assert_eq!(value.span.start.utf16_column, 7);
+}
@@ -1,5 +1,8 @@
 use crate::{
-	diagnostics::{Diagnostic, DiagnosticLabel, DiagnosticLabelPriority, DiagnosticType, Reporter},
+	diagnostics::{
+		parse_code, Applicability, Diagnostic, DiagnosticLabel, DiagnosticLabelPriority,
+		DiagnosticType, Reporter, Suggestion,
+	},
 	token::Token as lexerToken,
 	DataLiteral, Position,
 };
@@ -9,6 +12,7 @@ use indexmap::{map, IndexMap};
 use smartstring::alias::String;
 use std::{
 	borrow::Borrow,
+	cell::{Cell, RefCell},
 	fmt::Debug,
 	hash::Hash,
 	iter::{self, Peekable},
@@ -54,13 +58,6 @@ pub struct Taml<'a, Position> {
 }
 
 impl<'a, Position> Taml<'a, Position> {
-	fn unwrap_list_mut(&mut self) -> &mut List<'a, Position> {
-		match &mut self.value {
-			TamlValue::List(list) => list,
-			_ => panic!("Expected list."),
-		}
-	}
-
 	fn unwrap_map_mut(&mut self) -> &mut Map<'a, Position> {
 		match &mut self.value {
 			TamlValue::Map(map) => map,
@@ -91,6 +88,10 @@ pub enum TamlValue<'a, Position> {
 		key: Key<'a, Position>,
 		payload: VariantPayload<'a, Position>,
 	},
+	/// Placeholder for a value that could not be parsed.
+	///
+	/// Only [`parse_with`] in [`ParseMode::Recovering`] produces this.
+	Error,
 }
 
 #[derive(Debug, Clone)]
@@ -100,15 +101,15 @@ pub enum VariantPayload<'a, Position> {
 	Unit,
 }
 
-struct PathSegment<'a, P: Position> {
-	base: Vec<BasicPathElement<'a, P>>,
-	tabular: Option<TabularPathSegment<'a, P>>,
+pub(crate) struct PathSegment<'a, P: Position> {
+	pub(crate) base: Vec<BasicPathElement<'a, P>>,
+	pub(crate) tabular: Option<TabularPathSegment<'a, P>>,
 }
 
 #[derive(Clone)]
-struct BasicPathElement<'a, P: Position> {
-	key: BasicPathElementKey<'a, P>,
-	variant: Option<Key<'a, P>>,
+pub(crate) struct BasicPathElement<'a, P: Position> {
+	pub(crate) key: BasicPathElementKey<'a, P>,
+	pub(crate) variant: Option<Key<'a, P>>,
 }
 
 impl<'a, P: Position> BasicPathElement<'a, P> {
@@ -121,7 +122,7 @@ impl<'a, P: Position> BasicPathElement<'a, P> {
 }
 
 #[derive(Clone)]
-enum BasicPathElementKey<'a, Position> {
+pub(crate) enum BasicPathElementKey<'a, Position> {
 	Plain(Key<'a, Position>),
 	List {
 		key: Key<'a, Position>,
@@ -138,8 +139,8 @@ impl<'a, Position> BasicPathElementKey<'a, Position> {
 	}
 }
 
-struct TabularPathSegment<'a, P: Position> {
-	base: Vec<BasicPathElement<'a, P>>,
+pub(crate) struct TabularPathSegment<'a, P: Position> {
+	pub(crate) base: Vec<BasicPathElement<'a, P>>,
 	multi: Option<(Vec<TabularPathSegment<'a, P>>, Range<P>)>,
 }
 
@@ -188,7 +189,7 @@ pub type List<'a, Position> = Vec<Taml<'a, Position>>;
 pub type ListIter<'iter, 'taml, Position> = std::slice::Iter<'iter, Taml<'taml, Position>>;
 
 impl<'a, P: Position> TabularPathSegment<'a, P> {
-	fn arity(&self) -> usize {
+	pub(crate) fn arity(&self) -> usize {
 		match &self.multi {
 			None => 1,
 			Some(multi) => multi.0.iter().map(Self::arity).sum(),
@@ -203,6 +204,7 @@ impl<'a, P: Position> TabularPathSegment<'a, P> {
 	) -> Result<(), ()> {
 		#![allow(clippy::items_after_statements)]
 		#![allow(clippy::option_if_let_else)]
+		#![allow(clippy::too_many_lines)]
 
 		if self.base.is_empty() && self.multi.is_none() {
 			//TODO: Make sure these aren't accepted by the parser.
@@ -221,29 +223,25 @@ impl<'a, P: Position> TabularPathSegment<'a, P> {
 			reporter,
 		)?;
 
+		// Remains in place if this row can't be assigned in recovering mode.
 		fn placeholder<'a, P: Position>(position: P) -> Taml<'a, P> {
 			Taml {
 				span: position.clone()..position,
-				value: TamlValue::String(Cow::Borrowed("PLACEHOLDER")),
+				value: TamlValue::Error,
 			}
 		}
 
 		let selection: &mut Taml<'a, P> = match &self.base.last().unwrap().key {
-			BasicPathElementKey::Plain(key) => try_match!(
-				map::Entry::Vacant(vacant)
-				= selection.entry(key.clone())
-				=> vacant.insert(placeholder(key.span.start.clone()))
-			)
-			.debugless_unwrap(),
+			BasicPathElementKey::Plain(key) => match selection.entry(key.clone()) {
+				map::Entry::Vacant(vacant) => vacant.insert(placeholder(key.span.start.clone())),
+				map::Entry::Occupied(occupied) => {
+					reporter.report_with(|| key_previously_defined(occupied.key(), key));
+					return Err(());
+				}
+			},
 
 			BasicPathElementKey::List { key, span } => {
-				let list = selection
-					.entry(key.clone())
-					.or_insert_with(|| Taml {
-						value: TamlValue::List(vec![]),
-						span: span.clone(),
-					})
-					.unwrap_list_mut();
+				let list = select_list(selection, key, span, reporter)?;
 				list.push(placeholder(span.start.clone()));
 				list.last_mut().unwrap()
 			}
@@ -251,7 +249,6 @@ impl<'a, P: Position> TabularPathSegment<'a, P> {
 
 		let variant = self.base.last().unwrap().variant.as_ref();
 
-		//TODO: Report not enough values.
 		if let Some(multi) = &self.multi {
 			let selection = if let Some(variant) = variant {
 				*selection = Taml {
@@ -276,8 +273,11 @@ impl<'a, P: Position> TabularPathSegment<'a, P> {
 			}
 			Ok(())
 		} else {
+			let value = values
+				.next()
+				.expect("Rows are only assigned once they have a value for each column.");
 			if let Some(variant) = variant {
-				*selection = match values.next().ok_or(())? {
+				*selection = match value {
 					Taml {
 						span,
 						value: TamlValue::List(list),
@@ -288,10 +288,29 @@ impl<'a, P: Position> TabularPathSegment<'a, P> {
 							payload: VariantPayload::Tuple(list),
 						},
 					},
-					_ => return Err(()), //TODO: Report
+					Taml { span, .. } => {
+						reporter.report_with(|| {
+							Diagnostic::new(
+								DiagnosticType::TupleVariantValueNotAList,
+								vec![
+									DiagnosticLabel::new(
+										"This column is declared as tuple variant...",
+										variant.span.clone(),
+										DiagnosticLabelPriority::Auxiliary,
+									),
+									DiagnosticLabel::new(
+										"...but its value here is not a list.",
+										span,
+										DiagnosticLabelPriority::Primary,
+									),
+								],
+							)
+						});
+						return Err(());
+					}
 				};
 			} else {
-				*selection = values.next().ok_or(())?;
+				*selection = value;
 			}
 			Ok(())
 		}
@@ -309,16 +328,163 @@ pub fn parse<'a, P: Position>(
 	iter: impl IntoIterator<Item = impl IntoToken<'a, P>>,
 	reporter: &mut impl Reporter<P>,
 ) -> Result<Map<'a, P>, ()> {
+	parse_with(iter, reporter, ParseMode::Strict).0.ok_or(())
+}
+
+/// How [`parse_with`] handles invalid input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+	/// Stop at the first structural error, like [`parse`].
+	Strict,
+	/// Report each error, skip to the next line and continue parsing.
+	///
+	/// Lines in sections with an invalid heading are skipped, apart from reporting unrecognised tokens.
+	/// Missing or invalid values after a key are represented by [`TamlValue::Error`].
+	Recovering,
+}
+
+impl Default for ParseMode {
+	fn default() -> Self {
+		Self::Strict
+	}
+}
+
+/// Parses TAML tokens into a map representing the contained structure, handling errors according to `mode`.
+///
+/// Any errors and warnings are reported via `reporter`, once parsing is complete.
+///
+/// A comment of the form `// taml-allow: P0013, P0004` suppresses diagnostics with the listed codes
+/// whose first primary label starts on the same line, or on the next line if the comment is on a line of its own.
+/// Suppressed diagnostics are not reported, but still count as errors for the return value.
+/// Positions without location, like `()`, disable suppressions.
+///
+/// Returns the parsed map (if any) and whether any errors were found.
+/// With [`ParseMode::Recovering`], the map is always [`Some`] and contains everything that could be parsed.
+pub fn parse_with<'a, P: Position>(
+	iter: impl IntoIterator<Item = impl IntoToken<'a, P>>,
+	reporter: &mut impl Reporter<P>,
+	mode: ParseMode,
+) -> (Option<Map<'a, P>>, bool) {
+	let suppressions = RefCell::new(Suppressions::default());
+	let mut diagnostics = vec![];
+	let result = parse_lines(
+		iter.into_iter()
+			.map(IntoToken::into_token)
+			.inspect(|token| suppressions.borrow_mut().track(token)),
+		&mut diagnostics,
+		mode,
+	);
+
+	let suppressions = suppressions.into_inner();
+	reporter.report_many_with(|| {
+		diagnostics
+			.into_iter()
+			.filter(move |diagnostic| !suppressions.allows(diagnostic))
+	});
+	result
+}
+
+/// Collects `// taml-allow: …` comments, see [`parse_with`].
+struct Suppressions<P> {
+	/// The start of each [`lexerToken::Newline`] so far.
+	line_breaks: Vec<P>,
+	at_line_start: bool,
+	/// The start of the previous token.
+	previous_start: Option<P>,
+	/// Whether positions advance, which they don't without location (e.g. for `()`).
+	located: bool,
+	/// Suppressed diagnostic codes by 0-based line, as group code and number.
+	///
+	/// Diagnostic types are not distinct here, since some share a code.
+	allowed: Vec<(usize, (char, usize))>,
+}
+
+impl<P> Default for Suppressions<P> {
+	fn default() -> Self {
+		Self {
+			line_breaks: vec![],
+			at_line_start: true,
+			previous_start: None,
+			located: true,
+			allowed: vec![],
+		}
+	}
+}
+
+impl<P: Position> Suppressions<P> {
+	fn track(&mut self, token: &Token<P>) {
+		if let Some(previous_start) = self.previous_start.replace(token.span.start.clone()) {
+			self.located &= previous_start < token.span.start;
+		}
+
+		match &token.token {
+			lexerToken::Newline => {
+				self.line_breaks.push(token.span.start.clone());
+				self.at_line_start = true;
+				return;
+			}
+			lexerToken::Comment(comment) => {
+				if let Some(codes) = comment.trim_start().strip_prefix("taml-allow:") {
+					let line = self.line_breaks.len() + usize::from(self.at_line_start);
+					self.allowed.extend(
+						codes
+							.split(|c: char| c == ',' || c.is_whitespace())
+							.filter_map(parse_code)
+							.map(|code| (line, code)),
+					);
+				}
+			}
+			_ => (),
+		}
+		self.at_line_start = false;
+	}
+
+	fn allows(&self, diagnostic: &Diagnostic<P>) -> bool {
+		// Without location, every diagnostic would appear to be on the first line.
+		self.located
+			&& diagnostic
+				.labels
+				.iter()
+				.filter(|label| label.priority == DiagnosticLabelPriority::Primary)
+				.find_map(|label| label.span.as_ref())
+				.map_or(false, |span| {
+					let line = self
+						.line_breaks
+						.partition_point(|line_break| line_break < &span.start);
+					self.allowed
+						.contains(&(line, diagnostic.type_.code_parts()))
+				})
+	}
+}
+
+fn parse_lines<'a, P: Position>(
+	iter: impl Iterator<Item = Token<'a, P>>,
+	reporter: &mut impl Reporter<P>,
+	mode: ParseMode,
+) -> (Option<Map<'a, P>>, bool) {
 	#![allow(clippy::items_after_statements)]
 	#![allow(clippy::too_many_lines)]
 
-	let mut iter = iter.into_iter().map(IntoToken::into_token).peekable();
+	// Counts newlines taken from the input (including a peeked one),
+	// so that recovery can tell whether a failed parser already consumed a line break.
+	let newlines = Cell::new(0_usize);
+	let mut iter = iter
+		.inspect(|token| {
+			if token.token == lexerToken::Newline {
+				newlines.set(newlines.get() + 1)
+			}
+		})
+		.peekable();
+	let mut had_errors = false;
+	// The depth of the last heading that could not be parsed or applied, if its section is still open.
+	let mut broken_section = None;
 
 	let mut taml = Map::new();
 
 	let mut path = vec![];
 
-	let mut selection = &mut taml;
+	// [`None`] while in a section with invalid heading.
+	let mut selection = Some(&mut taml);
 
 	#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 	enum ParserState {
@@ -345,213 +511,302 @@ pub fn parse<'a, P: Position>(
 
 	let mut state = ParserState::LineStart;
 	while let Some(next) = iter.peek().map(|t| &t.token) {
-		state = match next {
-			lexerToken::Error => {
-				// Stop parsing but collect all the tokenizer reporter.
-				reporter.report_many_with(|| {
-					iter.filter_map(|t| {
-						if let Token {
-							token: lexerToken::Error,
+		let line_start_newlines = newlines.get() - usize::from(next == &lexerToken::Newline);
+		// Runs only once, so that failed parsers can `break 'token Err(())`.
+		#[allow(clippy::never_loop)]
+		let result: Result<ParserState, ()> = 'token: loop {
+			break Ok(match next {
+				lexerToken::Error if mode == ParseMode::Recovering => {
+					let span = iter.next().expect("unreachable").span;
+					reporter.report_with(|| unrecognized_token(span));
+					break 'token Err(());
+				}
+				lexerToken::Error => {
+					// Stop parsing but collect all the tokenizer reporter.
+					let iter = &mut iter;
+					reporter.report_many_with(|| {
+						iter.filter_map(|t| {
+							if let Token {
+								token: lexerToken::Error,
+								span,
+							} = t
+							{
+								Some(span)
+							} else {
+								None
+							}
+						})
+						.map(unrecognized_token)
+					});
+					break 'token Err(());
+				}
+
+				lexerToken::Comment(_) if state.can_comment() => {
+					let comment = iter.next();
+					debug_assert!(matches!(
+						comment.expect("unreachable").token,
+						lexerToken::Comment(_)
+					));
+					ParserState::Comment
+				}
+				lexerToken::Comment(_) => {
+					had_errors = true;
+					reporter.report_with(|| Diagnostic::new(
+						DiagnosticType::MisplacedComment,
+						vec![DiagnosticLabel::new(
+							"This comment appears after another comment without newline in-between, which shouldn't be possible.",
+							iter.next().expect("unreachable").span,
+							DiagnosticLabelPriority::Primary,
+						)],
+					));
+					ParserState::Comment
+				}
+
+				lexerToken::HeadingHashes(_) if state.can_heading() => {
+					let (depth, hashes_span) = match iter.next().expect("unreachable") {
+						Token {
+							token: lexerToken::HeadingHashes(count),
 							span,
-						} = t
+						} => (count, span),
+						_ => unreachable!(),
+					};
+
+					if matches!(broken_section, Some(broken_depth) if depth > broken_depth) {
+						// Part of a section that's already been reported as invalid.
+						skip_line(&mut iter, reporter, &mut had_errors);
+						break 'token Ok(ParserState::Other);
+					}
+					broken_section = Some(depth);
+					selection = None;
+
+					path.truncate(depth - 1);
+					if path.len() != depth - 1 {
+						reporter.report_with(|| {
+							Diagnostic::new(
+								DiagnosticType::HeadingTooDeep,
+								vec![
+									DiagnosticLabel::new(
+										"This heading is nested more than one level deeper than the previous one.",
+										hashes_span,
+										DiagnosticLabelPriority::Primary,
+									)
+								],
+							)
+						});
+						break 'token Err(());
+					}
+
+					if path
+						.last()
+						.and_then(|s: &PathSegment<P>| s.tabular.as_ref())
+						.is_some()
+					{
+						reporter.report_with(|| {
+							Diagnostic::new(
+								DiagnosticType::SubsectionInTabularSection,
+								vec![
+									DiagnosticLabel::new(
+										"This heading is nested inside a tabular section, which is not supported.",
+										hashes_span,
+										DiagnosticLabelPriority::Primary,
+									)
+								],
+							)
+						});
+						break 'token Err(());
+					}
+
+					let new_segment = match parse_path_segment(&mut iter, reporter) {
+						Ok(new_segment) => new_segment,
+						Err(()) => break 'token Err(()),
+					};
+
+					let new_selection = match instantiate(
+						get_last_mut(&mut taml, path.iter()),
+						new_segment.base.iter().cloned(),
+						reporter,
+					) {
+						Ok(new_selection) => new_selection,
+						Err(()) => break 'token Err(()),
+					};
+
+					if let Some(tabular) = new_segment.tabular.as_ref() {
+						// Create lists for empty headings too.
+						if let BasicPathElement {
+							key: BasicPathElementKey::List { key, span },
+							variant: _,
+						} = tabular.base.first().expect("unreachable")
 						{
-							Some(span)
+							if select_list(new_selection, key, span, reporter).is_err() {
+								break 'token Err(());
+							}
 						} else {
-							None
+							unreachable!()
 						}
-					})
-					.map(|span| Diagnostic {
-						type_: DiagnosticType::UnrecognizedToken,
-						labels: vec![DiagnosticLabel::new::<&'static str, _, _>(
-							None,
-							span,
-							DiagnosticLabelPriority::Primary,
-						)],
-					})
-				});
-				return Err(());
-			}
-
-			lexerToken::Comment(_) if state.can_comment() => {
-				let comment = iter.next();
-				debug_assert!(matches!(
-					comment.expect("unreachable").token,
-					lexerToken::Comment(_)
-				));
-				ParserState::Comment
-			}
-			lexerToken::Comment(_) => {
-				reporter.report_with(|| Diagnostic {
-                    type_: DiagnosticType::MisplacedComment,
-                    labels: vec![DiagnosticLabel::new(
-                        "This comment appears after another comment without newline in-between, which shouldn't be possible.",
-                        iter.next().expect("unreachable").span,
-                        DiagnosticLabelPriority::Primary,
-                    )]
-                });
-				ParserState::Comment
-			}
+					}
 
-			lexerToken::HeadingHashes(_) if state.can_heading() => {
-				let (depth, hashes_span) = match iter.next().expect("unreachable") {
-					Token {
-						token: lexerToken::HeadingHashes(count),
-						span,
-					} => (count, span),
-					_ => unreachable!(),
-				};
+					path.push(new_segment);
+					selection = Some(new_selection);
+					broken_section = None;
 
-				path.truncate(depth - 1);
-				if path.len() != depth - 1 {
-					reporter.report_with(|| Diagnostic {
-                            type_: DiagnosticType::HeadingTooDeep,
-                            labels: vec![
-                                DiagnosticLabel::new(
-                                    "This heading is nested more than one level deeper than the previous one.",
-                                    hashes_span,
-                                    DiagnosticLabelPriority::Primary,
-                                )
-                            ],
-                        });
-					return Err(());
+					ParserState::Other
+				}
+				lexerToken::HeadingHashes(_) => {
+					had_errors = true;
+					let start = iter.next().expect("unreachable").span.start;
+					reporter.report_with(|| {
+						Diagnostic::new(
+							DiagnosticType::MisplacedHeading,
+							vec![DiagnosticLabel::new(
+								"Expected newline before heading.",
+								start.clone()..start,
+								DiagnosticLabelPriority::Primary,
+							)],
+						)
+					});
+					ParserState::Comment
 				}
 
-				if path
-					.last()
-					.and_then(|s: &PathSegment<P>| s.tabular.as_ref())
-					.is_some()
-				{
-					reporter.report_with(|| Diagnostic {
-                            type_: DiagnosticType::SubsectionInTabularSection,
-                            labels: vec![
-                                DiagnosticLabel::new(
-                                    "This heading is nested inside a tabular section, which is not supported.",
-                                    hashes_span,
-                                    DiagnosticLabelPriority::Primary,
-                                )
-                            ],
-                        });
-					return Err(());
+				lexerToken::Newline => {
+					let newline = iter.next();
+					debug_assert_eq!(newline.expect("unreachable").token, lexerToken::Newline);
+					ParserState::LineStart
 				}
 
-				let new_segment = match parse_path_segment(&mut iter, reporter) {
-					Ok(new_segment) => new_segment,
-					Err(()) => return Err(()),
-				};
+				// Data
+				_ if state.can_data() && broken_section.is_some() => {
+					skip_line(&mut iter, reporter, &mut had_errors);
+					ParserState::Other
+				}
+				_ if state.can_data() => {
+					let selection = selection
+						.as_deref_mut()
+						.expect("Sections with invalid heading are skipped.");
+					#[allow(clippy::single_match_else)]
+					match path.last().and_then(|s| s.tabular.as_ref()) {
+						Some(tabular) => {
+							let n = tabular.arity();
+							let values = match parse_values_line(&mut iter, n, reporter) {
+								Ok(values) => values,
+								Err(()) => break 'token Err(()),
+							};
+
+							let mut values = values.into_iter();
+							if let Err(()) = tabular.assign(selection, &mut values, reporter) {
+								break 'token Err(());
+							}
 
-				selection = match instantiate(
-					get_last_mut(&mut taml, path.iter()),
-					new_segment.base.iter().cloned(),
-					reporter,
-				) {
-					Ok(selection) => selection,
-					Err(()) => return Err(()),
-				};
+							debug_assert!(values.next().is_none());
+						}
+						None => {
+							let (key, value_start) = match parse_key(&mut iter, reporter) {
+								Ok(key) => key,
+								Err(()) => break 'token Err(()),
+							};
+							let (value, error) = match parse_value(&mut iter, reporter) {
+								Ok(value) => (value, false),
+								Err(()) if mode == ParseMode::Recovering => (
+									Taml {
+										span: value_start.clone()..value_start,
+										value: TamlValue::Error,
+									},
+									true,
+								),
+								Err(()) => break 'token Err(()),
+							};
+							match selection.entry(key.clone()) {
+								map::Entry::Vacant(vacant) => {
+									vacant.insert(value);
+								}
+								map::Entry::Occupied(occupied) => {
+									reporter.report_with(|| {
+										key_previously_defined(occupied.key(), &key)
+									});
+									break 'token Err(());
+								}
+							}
+							if error {
+								break 'token Err(());
+							}
+						}
+					};
 
-				if let Some(tabular) = new_segment.tabular.as_ref() {
-					// Create lists for empty headings too.
-					if let BasicPathElement {
-						key: BasicPathElementKey::List { key, span },
-						variant: _,
-					} = tabular.base.first().expect("unreachable")
-					{
-						selection.entry(key.clone()).or_insert_with(|| Taml {
-							span: span.clone(),
-							value: TamlValue::List(List::new()),
-						});
-					} else {
-						unreachable!()
-					}
+					ParserState::Other
 				}
+				_ => {
+					had_errors = true;
+					let start = iter.next().expect("unreachable").span.start;
+					reporter.report_with(|| Diagnostic::new(
+						DiagnosticType::MisplacedData,
+						vec![DiagnosticLabel::new(
+							if path.last().and_then(|s| s.tabular.as_ref()).is_some() {
+								"Expected either a comma (to continue this row) or a newline (before the next table row) here."
+							} else {
+								"Expected a newline before next key-value-pair."
+							},
+							start.clone()..start,
+							DiagnosticLabelPriority::Primary,
+						)],
+					));
+					ParserState::Comment
+				}
+			});
+		};
 
-				path.push(new_segment);
-
-				ParserState::Other
-			}
-			lexerToken::HeadingHashes(_) => {
-				let start = iter.next().expect("unreachable").span.start;
-				reporter.report_with(|| Diagnostic {
-					type_: DiagnosticType::MisplacedHeading,
-					labels: vec![DiagnosticLabel::new(
-						"Expected newline before heading.",
-						start.clone()..start,
-						DiagnosticLabelPriority::Primary,
-					)],
-				});
-				ParserState::Comment
+		state = if let Ok(state) = result {
+			state
+		} else {
+			had_errors = true;
+			if mode == ParseMode::Strict {
+				return (None, true);
 			}
 
-			lexerToken::Newline => {
-				let newline = iter.next();
-				debug_assert_eq!(newline.expect("unreachable").token, lexerToken::Newline);
+			// Resynchronise at the next line, unless the failed parser already consumed a line break.
+			let peeked_newline = matches!(
+				iter.peek(),
+				Some(Token {
+					token: lexerToken::Newline,
+					..
+				})
+			);
+			if newlines.get() - usize::from(peeked_newline) > line_start_newlines {
 				ParserState::LineStart
+			} else {
+				skip_line(&mut iter, reporter, &mut had_errors);
+				ParserState::Other
 			}
+		};
+	}
 
-			// Data
-			_ if state.can_data() => {
-				#[allow(clippy::single_match_else)]
-				match path.last().and_then(|s| s.tabular.as_ref()) {
-					Some(tabular) => {
-						let n = tabular.arity();
-						let values = match parse_values_line(&mut iter, n, reporter) {
-							Ok(values) => values,
-							Err(()) => return Err(()),
-						};
-
-						let mut values = values.into_iter();
-						if let Err(()) = tabular.assign(selection, &mut values, reporter) {
-							return Err(());
-						};
-
-						debug_assert!(values.next().is_none());
-					}
-					None => {
-						let (key, value) = match parse_key_value_pair(&mut iter, reporter) {
-							Ok(kv) => kv,
-							Err(()) => return Err(()),
-						};
-						//TODO: Also report occupied.
-						if let map::Entry::Vacant(vacant) = selection.entry(key.clone()) {
-							vacant.insert(value);
-						} else {
-							reporter.report_with(|| Diagnostic {
-								type_: DiagnosticType::KeyPreviouslyDefined,
-								labels: vec![DiagnosticLabel::new(
-									"This key has already been assigned a value.",
-									key.span,
-									DiagnosticLabelPriority::Primary,
-								)],
-							});
-							return Err(());
-						}
-					}
-				};
+	(Some(taml), had_errors)
+}
 
-				ParserState::Other
-			}
-			_ => {
-				let start = iter.next().expect("unreachable").span.start;
-				reporter.report_with(|| Diagnostic {
-					type_: DiagnosticType::MisplacedData,
-					labels: vec![DiagnosticLabel::new(
-						if path.last().and_then(|s| s.tabular.as_ref()).is_some() {
-							"Expected either a comma (to continue this row) or a newline (before the next table row) here."
-						} else {
-							"Expected a newline before next key-value-pair."
-						},
-						start.clone()..start,
-						DiagnosticLabelPriority::Primary,
-					)],
-				});
-				ParserState::Comment
-			}
+/// Skips tokens up to (but excluding) the next newline, reporting unrecognised tokens.
+fn skip_line<'a, P: Position>(
+	iter: &mut Peekable<impl Iterator<Item = Token<'a, P>>>,
+	reporter: &mut impl Reporter<P>,
+	had_errors: &mut bool,
+) {
+	while let Some(token) = iter.next_if(|t| t.token != lexerToken::Newline) {
+		if token.token == lexerToken::Error {
+			*had_errors = true;
+			reporter.report_with(|| unrecognized_token(token.span));
 		}
 	}
+}
 
-	Ok(taml)
+fn unrecognized_token<P>(span: Range<P>) -> Diagnostic<P> {
+	Diagnostic::new(
+		DiagnosticType::UnrecognizedToken,
+		vec![DiagnosticLabel::new::<&'static str, _, _>(
+			None,
+			span,
+			DiagnosticLabelPriority::Primary,
+		)],
+	)
 }
 
-fn parse_path_segment<'a, 'b, 'c, P: Position>(
+pub(crate) fn parse_path_segment<'a, 'b, 'c, P: Position>(
 	iter: &mut Peekable<impl Iterator<Item = Token<'a, P>>>,
 	reporter: &mut impl Reporter<P>,
 ) -> Result<PathSegment<'a, P>, ()> {
@@ -581,18 +836,27 @@ fn parse_path_segment<'a, 'b, 'c, P: Position>(
 				base.push(BasicPathElement {
                     key: BasicPathElementKey::Plain(Key{name:key, span:key_span}),
                     variant: if iter.peek().map(|t| &t.token) == Some(&lexerToken::Colon) {
-                        assert_eq!(iter.next().unwrap().token, lexerToken::Colon);
+                        let colon = iter.next().unwrap();
+                        assert_eq!(colon.token, lexerToken::Colon);
                         if !matches!(
                             iter.peek().map(|t| &t.token),
                             Some(lexerToken::Identifier(_))
                         ) {
                             reporter.report_with(||Diagnostic {
-                                type_: DiagnosticType::MissingVariantIdentifier,
-                                labels: vec![DiagnosticLabel::new(
-                                    "Colons in (non-tabular) paths must be followed by a variant identifier (for a structured enum).",
-                                    iter.next().map(|t| t.span),
-                                    DiagnosticLabelPriority::Primary,
+                                suggestions: vec![Suggestion::new(
+                                    "Remove the colon.",
+                                    colon.span,
+                                    "",
+                                    Applicability::MaybeIncorrect,
                                 )],
+                                ..Diagnostic::new(
+                                    DiagnosticType::MissingVariantIdentifier,
+                                    vec![DiagnosticLabel::new(
+                                        "Colons in (non-tabular) paths must be followed by a variant identifier (for a structured enum).",
+                                        iter.next().map(|t| t.span),
+                                        DiagnosticLabelPriority::Primary,
+                                    )],
+                                )
                             });
                             return Err(());
                         }
@@ -634,20 +898,22 @@ fn parse_path_segment<'a, 'b, 'c, P: Position>(
 							)
 							.debugless_unwrap()
 						} else {
-							reporter.report_with(|| Diagnostic {
-								type_: DiagnosticType::UnclosedListKey,
-								labels: vec![
-									DiagnosticLabel::new(
-										"The list key is opened here...",
-										brac_span,
-										DiagnosticLabelPriority::Auxiliary,
-									),
-									DiagnosticLabel::new(
-										"...but not closed at this point.\nExpected ].",
-										iter.next().map(|t| t.span.start.clone()..t.span.start),
-										DiagnosticLabelPriority::Primary,
-									),
-								],
+							reporter.report_with(|| {
+								Diagnostic::new(
+									DiagnosticType::UnclosedListKey,
+									vec![
+										DiagnosticLabel::new(
+											"The list key is opened here...",
+											brac_span,
+											DiagnosticLabelPriority::Auxiliary,
+										),
+										DiagnosticLabel::new(
+											"...but not closed at this point.\nExpected ].",
+											iter.next().map(|t| t.span.start.clone()..t.span.start),
+											DiagnosticLabelPriority::Primary,
+										),
+									],
+								)
 							});
 							return Err(());
 						};
@@ -660,18 +926,27 @@ fn parse_path_segment<'a, 'b, 'c, P: Position>(
 								span: brac_span.start..ket_end,
 							},
 							variant: if iter.peek().map(|t| &t.token) == Some(&lexerToken::Colon) {
-								assert_eq!(iter.next().unwrap().token, lexerToken::Colon);
+								let colon = iter.next().unwrap();
+								assert_eq!(colon.token, lexerToken::Colon);
 								if !matches!(
 									iter.peek().map(|t| &t.token),
 									Some(lexerToken::Identifier(_))
 								) {
 									reporter.report_with(|| Diagnostic {
-										type_: DiagnosticType::MissingVariantIdentifier,
-										labels: vec![DiagnosticLabel::new(
-											"Colons in headings must be followed by an identifier.",
-											iter.next().map(|t| t.span),
-											DiagnosticLabelPriority::Primary,
+										suggestions: vec![Suggestion::new(
+											"Remove the colon.",
+											colon.span,
+											"",
+											Applicability::MaybeIncorrect,
 										)],
+										..Diagnostic::new(
+											DiagnosticType::MissingVariantIdentifier,
+											vec![DiagnosticLabel::new(
+												"Colons in headings must be followed by an identifier.",
+												iter.next().map(|t| t.span),
+												DiagnosticLabelPriority::Primary,
+											)],
+										)
 									});
 									return Err(());
 								}
@@ -696,20 +971,22 @@ fn parse_path_segment<'a, 'b, 'c, P: Position>(
 						if let Some(lexerToken::Ket) = iter.peek().map(|t| &t.token) {
 							assert_eq!(iter.next().unwrap().token, lexerToken::Ket)
 						} else {
-							reporter.report_with(|| Diagnostic {
-								type_: DiagnosticType::UnclosedTabularPathSection,
-								labels: vec![
-									DiagnosticLabel::new(
-										"The tabular section is opened here...",
-										brac_span,
-										DiagnosticLabelPriority::Auxiliary,
-									),
-									DiagnosticLabel::new(
-										"...but not closed at this point.\nExpected ].",
-										iter.next().map(|t| t.span.start.clone()..t.span.start),
-										DiagnosticLabelPriority::Primary,
-									),
-								],
+							reporter.report_with(|| {
+								Diagnostic::new(
+									DiagnosticType::UnclosedTabularPathSection,
+									vec![
+										DiagnosticLabel::new(
+											"The tabular section is opened here...",
+											brac_span,
+											DiagnosticLabelPriority::Auxiliary,
+										),
+										DiagnosticLabel::new(
+											"...but not closed at this point.\nExpected ].",
+											iter.next().map(|t| t.span.start.clone()..t.span.start),
+											DiagnosticLabelPriority::Primary,
+										),
+									],
+								)
 							});
 							return Err(());
 						}
@@ -717,34 +994,37 @@ fn parse_path_segment<'a, 'b, 'c, P: Position>(
 
 					Some(lexerToken::InvalidIdentifierWithVerbatimCarriageReturn(str)) => {
 						let str = *str;
-						reporter.report_with(|| Diagnostic {
-							type_: DiagnosticType::VerbatimCarriageReturnInsideLiteral,
-							labels: cr_labels(str, iter.next().unwrap().span, Some('`')),
+						reporter.report_with(|| {
+							cr_diagnostic(str, iter.next().unwrap().span, Some('`'))
 						});
 						return Err(());
 					}
 
 					_ => {
-						reporter.report_with(|| Diagnostic {
-							type_: DiagnosticType::ExpectedPathSegment,
-							labels: vec![DiagnosticLabel::new(
-								"Expected [ or an identifier here.",
-								iter.next().map(|t| t.span),
-								DiagnosticLabelPriority::Primary,
-							)],
+						reporter.report_with(|| {
+							Diagnostic::new(
+								DiagnosticType::ExpectedPathSegment,
+								vec![DiagnosticLabel::new(
+									"Expected [ or an identifier here.",
+									iter.next().map(|t| t.span),
+									DiagnosticLabelPriority::Primary,
+								)],
+							)
 						});
 						return Err(());
 					}
 				}
 			}
 			Some(_) => {
-				reporter.report_with(|| Diagnostic {
-					type_: DiagnosticType::ExpectedPathSegment,
-					labels: vec![DiagnosticLabel::new(
-						"Expected [ or an identifier here.",
-						iter.next().map(|t| t.span),
-						DiagnosticLabelPriority::Primary,
-					)],
+				reporter.report_with(|| {
+					Diagnostic::new(
+						DiagnosticType::ExpectedPathSegment,
+						vec![DiagnosticLabel::new(
+							"Expected [ or an identifier here.",
+							iter.next().map(|t| t.span),
+							DiagnosticLabelPriority::Primary,
+						)],
+					)
 				});
 				return Err(());
 			}
@@ -754,16 +1034,18 @@ fn parse_path_segment<'a, 'b, 'c, P: Position>(
 			break;
 		}
 		match iter.peek().map(|t| &t.token) {
-			Some(lexerToken::Newline | lexerToken::Comment(_)) => break,
+			None | Some(lexerToken::Newline | lexerToken::Comment(_)) => break,
 			Some(lexerToken::Period) => assert_eq!(iter.next().unwrap().token, lexerToken::Period),
 			_ => {
-				reporter.report_with(|| Diagnostic {
-					type_: DiagnosticType::InvalidPathContinuation,
-					labels: vec![DiagnosticLabel::new(
-						"Expected a period or end of line",
-						iter.next().map(|t| t.span),
-						DiagnosticLabelPriority::Primary,
-					)],
+				reporter.report_with(|| {
+					Diagnostic::new(
+						DiagnosticType::InvalidPathContinuation,
+						vec![DiagnosticLabel::new(
+							"Expected a period or end of line",
+							iter.next().map(|t| t.span),
+							DiagnosticLabelPriority::Primary,
+						)],
+					)
 				});
 				return Err(());
 			}
@@ -816,20 +1098,22 @@ fn parse_tabular_path_segment<'a, P: Position>(
 						multi: Some((multi, bra_span.start..ce.span.end)),
 					})
 				} else {
-					reporter.report_with(|| Diagnostic {
-						type_: DiagnosticType::UnclosedTabularPathMultiSegment,
-						labels: vec![
-							DiagnosticLabel::new(
-								"This multi segment starts here...",
-								bra_span,
-								DiagnosticLabelPriority::Auxiliary,
-							),
-							DiagnosticLabel::new(
-								"...but is not closed at this point.",
-								iter.next().map(|t| t.span.start.clone()..t.span.start),
-								DiagnosticLabelPriority::Primary,
-							),
-						],
+					reporter.report_with(|| {
+						Diagnostic::new(
+							DiagnosticType::UnclosedTabularPathMultiSegment,
+							vec![
+								DiagnosticLabel::new(
+									"This multi segment starts here...",
+									bra_span,
+									DiagnosticLabelPriority::Auxiliary,
+								),
+								DiagnosticLabel::new(
+									"...but is not closed at this point.",
+									iter.next().map(|t| t.span.start.clone()..t.span.start),
+									DiagnosticLabelPriority::Primary,
+								),
+							],
+						)
 					});
 					Err(())
 				};
@@ -851,18 +1135,27 @@ fn parse_tabular_path_segment<'a, P: Position>(
 						span,
 					}),
 					variant: if iter.peek().map(|t| &t.token) == Some(&lexerToken::Colon) {
-						assert_eq!(iter.next().unwrap().token, lexerToken::Colon);
+						let colon = iter.next().unwrap();
+						assert_eq!(colon.token, lexerToken::Colon);
 						if !matches!(
 							iter.peek().map(|t| &t.token),
 							Some(lexerToken::Identifier(_))
 						) {
 							reporter.report_with(|| Diagnostic {
-								type_: DiagnosticType::MissingVariantIdentifier,
-								labels: vec![DiagnosticLabel::new::<&'static str, _, _>(
-									None,
-									iter.next().map(|t| t.span.start.clone()..t.span.start),
-									DiagnosticLabelPriority::Primary,
+								suggestions: vec![Suggestion::new(
+									"Remove the colon.",
+									colon.span,
+									"",
+									Applicability::MaybeIncorrect,
 								)],
+								..Diagnostic::new(
+									DiagnosticType::MissingVariantIdentifier,
+									vec![DiagnosticLabel::new::<&'static str, _, _>(
+										None,
+										iter.next().map(|t| t.span.start.clone()..t.span.start),
+										DiagnosticLabelPriority::Primary,
+									)],
+								)
 							});
 							return Err(());
 						}
@@ -904,13 +1197,15 @@ fn parse_tabular_path_segment<'a, P: Position>(
 						assert_eq!(ket.token, lexerToken::Ket);
 						ket.span.end
 					} else {
-						reporter.report_with(|| Diagnostic {
-							type_: DiagnosticType::ExpectedListIdentifier,
-							labels: vec![DiagnosticLabel::new::<&'static str, _, _>(
-								None,
-								iter.next().map(|t| t.span),
-								DiagnosticLabelPriority::Primary,
-							)],
+						reporter.report_with(|| {
+							Diagnostic::new(
+								DiagnosticType::ExpectedListIdentifier,
+								vec![DiagnosticLabel::new::<&'static str, _, _>(
+									None,
+									iter.next().map(|t| t.span),
+									DiagnosticLabelPriority::Primary,
+								)],
+							)
 						});
 						return Err(());
 					};
@@ -923,7 +1218,8 @@ fn parse_tabular_path_segment<'a, P: Position>(
 							span: brac_start..ket_end,
 						},
 						variant: if iter.peek().map(|t| &t.token) == Some(&lexerToken::Colon) {
-							assert_eq!(iter.next().unwrap().token, lexerToken::Colon);
+							let colon = iter.next().unwrap();
+							assert_eq!(colon.token, lexerToken::Colon);
 							if matches!(
 								iter.peek().map(|t| &t.token),
 								Some(lexerToken::Identifier(_))
@@ -936,12 +1232,20 @@ fn parse_tabular_path_segment<'a, P: Position>(
 								.debugless_unwrap()
 							} else {
 								reporter.report_with(|| Diagnostic {
-									type_: DiagnosticType::MissingVariantIdentifier,
-									labels: vec![DiagnosticLabel::new(
-										"Colons in paths must be followed by a variant identifier.",
-										iter.next().map(|t| t.span),
-										DiagnosticLabelPriority::Primary,
+									suggestions: vec![Suggestion::new(
+										"Remove the colon.",
+										colon.span,
+										"",
+										Applicability::MaybeIncorrect,
 									)],
+									..Diagnostic::new(
+										DiagnosticType::MissingVariantIdentifier,
+										vec![DiagnosticLabel::new(
+											"Colons in paths must be followed by a variant identifier.",
+											iter.next().map(|t| t.span),
+											DiagnosticLabelPriority::Primary,
+										)],
+									)
 								});
 								return Err(());
 							}
@@ -950,13 +1254,15 @@ fn parse_tabular_path_segment<'a, P: Position>(
 						},
 					})
 				} else {
-					reporter.report_with(|| Diagnostic {
-						type_: DiagnosticType::ExpectedListIdentifier,
-						labels: vec![DiagnosticLabel::new::<&'static str, _, _>(
-							None,
-							iter.next().map(|t| t.span),
-							DiagnosticLabelPriority::Primary,
-						)],
+					reporter.report_with(|| {
+						Diagnostic::new(
+							DiagnosticType::ExpectedListIdentifier,
+							vec![DiagnosticLabel::new::<&'static str, _, _>(
+								None,
+								iter.next().map(|t| t.span),
+								DiagnosticLabelPriority::Primary,
+							)],
+						)
 					});
 					return Err(());
 				}
@@ -964,21 +1270,20 @@ fn parse_tabular_path_segment<'a, P: Position>(
 
 			Some(lexerToken::InvalidIdentifierWithVerbatimCarriageReturn(str)) => {
 				let str = *str;
-				reporter.report_with(|| Diagnostic {
-					type_: DiagnosticType::VerbatimCarriageReturnInsideLiteral,
-					labels: cr_labels(str, iter.next().unwrap().span, Some('`')),
-				});
+				reporter.report_with(|| cr_diagnostic(str, iter.next().unwrap().span, Some('`')));
 				return Err(());
 			}
 
 			_ => {
-				reporter.report_with(|| Diagnostic {
-					type_: DiagnosticType::ExpectedTabularPathSegment,
-					labels: vec![DiagnosticLabel::new(
-						"Expected {, [ or an identifier here.",
-						iter.next().map(|t| t.span),
-						DiagnosticLabelPriority::Primary,
-					)],
+				reporter.report_with(|| {
+					Diagnostic::new(
+						DiagnosticType::ExpectedTabularPathSegment,
+						vec![DiagnosticLabel::new(
+							"Expected {, [ or an identifier here.",
+							iter.next().map(|t| t.span),
+							DiagnosticLabelPriority::Primary,
+						)],
+					)
 				});
 				return Err(());
 			}
@@ -1052,39 +1357,43 @@ fn instantiate<'a, 'b, P: Position>(
 							value: TamlValue::Map(map),
 						} => map,
 						Taml { span, .. } => {
-							reporter.report_with(|| Diagnostic {
-								type_: DiagnosticType::NonMapValueSelected,
-								labels: vec![
+							reporter.report_with(|| {
+								Diagnostic::new(
+									DiagnosticType::NonMapValueSelected,
+									vec![
+										DiagnosticLabel::new(
+											"This key is assigned something other than a map here...",
+											span.clone(),
+											DiagnosticLabelPriority::Auxiliary,
+										),
+										DiagnosticLabel::new(
+											"...but is selected as map here.",
+											path_element.span(),
+											DiagnosticLabelPriority::Primary,
+										),
+									],
+								)
+							});
+							return Err(());
+						}
+					},
+					(true, Some(_)) => {
+						reporter.report_with(|| {
+							Diagnostic::new(
+								DiagnosticType::DuplicateEnumInstantiation,
+								vec![
 									DiagnosticLabel::new(
-										"This key is assigned something other than a map here...",
-										span.clone(),
+										"This enum value has already been assigned here...",
+										selection.get(key).unwrap().span.clone(),
 										DiagnosticLabelPriority::Auxiliary,
 									),
 									DiagnosticLabel::new(
-										"...but is selected as map here.",
+										"...but another value is instantiated here.",
 										path_element.span(),
 										DiagnosticLabelPriority::Primary,
 									),
 								],
-							});
-							return Err(());
-						}
-					},
-					(true, Some(_)) => {
-						reporter.report_with(|| Diagnostic {
-							type_: DiagnosticType::DuplicateEnumInstantiation,
-							labels: vec![
-								DiagnosticLabel::new(
-									"This enum value has already been assigned here...",
-									selection.get(key).unwrap().span.clone(),
-									DiagnosticLabelPriority::Auxiliary,
-								),
-								DiagnosticLabel::new(
-									"...but another value is instantiated here.",
-									path_element.span(),
-									DiagnosticLabelPriority::Primary,
-								),
-							],
+							)
 						});
 						return Err(());
 					}
@@ -1108,16 +1417,7 @@ fn instantiate<'a, 'b, P: Position>(
 				}
 			}
 			BasicPathElementKey::List { key, span } => {
-				let list = selection
-					.entry(key.clone())
-					.or_insert_with({
-						let span = span.clone();
-						|| Taml {
-							span,
-							value: TamlValue::List(vec![]),
-						}
-					})
-					.unwrap_list_mut();
+				let list = select_list(selection, key, span, reporter)?;
 
 				#[allow(clippy::option_if_let_else)]
 				if let Some(variant) = path_element.variant {
@@ -1142,10 +1442,70 @@ fn instantiate<'a, 'b, P: Position>(
 	Ok(selection)
 }
 
-fn parse_key_value_pair<'a, P: Position>(
+/// Selects the list at `key`, inserting an empty one spanning `span` if there's none yet.
+fn select_list<'a, 'b, P: Position>(
+	selection: &'a mut Map<'b, P>,
+	key: &Key<'b, P>,
+	span: &Range<P>,
+	reporter: &mut impl Reporter<P>,
+) -> Result<&'a mut List<'b, P>, ()> {
+	match selection.entry(key.clone()).or_insert_with(|| Taml {
+		span: span.clone(),
+		value: TamlValue::List(vec![]),
+	}) {
+		Taml {
+			value: TamlValue::List(list),
+			..
+		} => Ok(list),
+		Taml {
+			span: existing_span,
+			..
+		} => {
+			reporter.report_with(|| {
+				Diagnostic::new(
+					DiagnosticType::NonListValueSelected,
+					vec![
+						DiagnosticLabel::new(
+							"This key is assigned something other than a list here...",
+							existing_span.clone(),
+							DiagnosticLabelPriority::Auxiliary,
+						),
+						DiagnosticLabel::new(
+							"...but is selected as list here.",
+							span.clone(),
+							DiagnosticLabelPriority::Primary,
+						),
+					],
+				)
+			});
+			Err(())
+		}
+	}
+}
+
+fn key_previously_defined<P: Position>(existing: &Key<'_, P>, key: &Key<'_, P>) -> Diagnostic<P> {
+	Diagnostic::new(
+		DiagnosticType::KeyPreviouslyDefined,
+		vec![
+			DiagnosticLabel::new(
+				"Previously defined here.",
+				existing.span.clone(),
+				DiagnosticLabelPriority::Auxiliary,
+			),
+			DiagnosticLabel::new(
+				"This key has already been assigned a value.",
+				key.span.clone(),
+				DiagnosticLabelPriority::Primary,
+			),
+		],
+	)
+}
+
+/// Parses a key and the following colon, returning the key and the position after the colon.
+fn parse_key<'a, P: Position>(
 	iter: &mut Peekable<impl Iterator<Item = Token<'a, P>>>,
 	reporter: &mut impl Reporter<P>,
-) -> Result<(Key<'a, P>, Taml<'a, P>), ()> {
+) -> Result<(Key<'a, P>, P), ()> {
 	Ok(
 		if let Some(lexerToken::Identifier(_)) = iter.peek().map(|t| &t.token) {
 			let (key_name, key_span) = try_match!(
@@ -1160,41 +1520,34 @@ fn parse_key_value_pair<'a, P: Position>(
 				name: key_name,
 				span: key_span,
 			};
-			if matches!(
-				iter.peek(),
-				Some(&Token {
-					token: lexerToken::Colon,
-					..
-				})
-			) {
-				assert!(matches!(
-					iter.next().unwrap(),
-					Token {
-						token: lexerToken::Colon,
-						..
-					}
-				))
+			if let Some(Token {
+				token: lexerToken::Colon,
+				..
+			}) = iter.peek()
+			{
+				(key, iter.next().unwrap().span.end)
 			} else {
-				reporter.report_with(|| Diagnostic {
-					type_: DiagnosticType::ExpectedKeyValuePair,
-					labels: vec![DiagnosticLabel::new(
-						"Expected colon.",
-						iter.next().map(|t| t.span.start.clone()..t.span.start),
-						DiagnosticLabelPriority::Primary,
-					)],
+				reporter.report_with(|| {
+					Diagnostic::new(
+						DiagnosticType::ExpectedKeyValuePair,
+						vec![DiagnosticLabel::new(
+							"Expected colon.",
+							iter.next().map(|t| t.span.start.clone()..t.span.start),
+							DiagnosticLabelPriority::Primary,
+						)],
+					)
 				});
 				return Err(());
 			}
-			(key, parse_value(iter, reporter)?)
 		} else {
-			reporter.report_with(||Diagnostic {
-                type_: DiagnosticType::ExpectedKeyValuePair,
-                labels: vec![DiagnosticLabel ::new(
-                    "Structured sections can only contain subsections and key-value pairs.\nKey-value pairs must start with an identifier.",
-                    iter.next().map(|t| t.span),
-                    DiagnosticLabelPriority::Primary,
-                )],
-            });
+			reporter.report_with(||Diagnostic::new(
+				DiagnosticType::ExpectedKeyValuePair,
+				vec![DiagnosticLabel ::new(
+					"Structured sections can only contain subsections and key-value pairs.\nKey-value pairs must start with an identifier.",
+					iter.next().map(|t| t.span),
+					DiagnosticLabelPriority::Primary,
+				)],
+			));
 			return Err(());
 		},
 	)
@@ -1211,13 +1564,15 @@ fn parse_values_line<'a, P: Position>(
 			assert_eq!(iter.next().unwrap().token, lexerToken::Comma);
 			values.push(parse_value(iter, reporter)?)
 		} else {
-			reporter.report_with(|| Diagnostic {
-				type_: DiagnosticType::ValuesLineTooShort,
-				labels: vec![DiagnosticLabel::new(
-					"Expected comma here.",
-					iter.next().map(|t| t.span.start.clone()..t.span.start),
-					DiagnosticLabelPriority::Primary,
-				)],
+			reporter.report_with(|| {
+				Diagnostic::new(
+					DiagnosticType::ValuesLineTooShort,
+					vec![DiagnosticLabel::new(
+						"Expected comma here.",
+						iter.next().map(|t| t.span.start.clone()..t.span.start),
+						DiagnosticLabelPriority::Primary,
+					)],
+				)
 			});
 			return Err(());
 		}
@@ -1238,13 +1593,15 @@ fn parse_value<'a, P: Position>(
 		span: impl Into<Option<Range<Position>>>,
 		reporter: &mut impl Reporter<Position>,
 	) -> Result<Taml<'a, Position>, ()> {
-		reporter.report_with(|| Diagnostic {
-			type_: DiagnosticType::ExpectedValue,
-			labels: vec![DiagnosticLabel::new::<&'static str, _, _>(
-				None,
-				span,
-				DiagnosticLabelPriority::Primary,
-			)],
+		reporter.report_with(|| {
+			Diagnostic::new(
+				DiagnosticType::ExpectedValue,
+				vec![DiagnosticLabel::new::<&'static str, _, _>(
+					None,
+					span,
+					DiagnosticLabelPriority::Primary,
+				)],
+			)
 		});
 		Err(())
 	}
@@ -1253,6 +1610,7 @@ fn parse_value<'a, P: Position>(
 		Ok(match (token, span) {
 			(lexerToken::Paren, paren_span) => {
 				let mut items = vec![];
+				let mut end = paren_span.end.clone();
 				while iter.peek().map(|t| &t.token) != Some(&lexerToken::Thesis) {
 					if matches!(
 						iter.peek().map(|t| &t.token),
@@ -1262,10 +1620,14 @@ fn parse_value<'a, P: Position>(
 						break;
 					}
 
-					items.push(parse_value(iter, reporter)?);
+					let item = parse_value(iter, reporter)?;
+					end = item.span.end.clone();
+					items.push(item);
 					match iter.peek().map(|t| &t.token) {
 						Some(lexerToken::Comma) => {
-							assert_eq!(iter.next().unwrap().token, lexerToken::Comma)
+							let comma = iter.next().unwrap();
+							assert_eq!(comma.token, lexerToken::Comma);
+							end = comma.span.end;
 						}
 						_ => break,
 					}
@@ -1279,19 +1641,27 @@ fn parse_value<'a, P: Position>(
 					}
 				} else {
 					reporter.report_with(|| Diagnostic {
-						type_: DiagnosticType::UnclosedList,
-						labels: vec![
-							DiagnosticLabel::new(
-								"This list starts here...",
-								paren_span,
-								DiagnosticLabelPriority::Auxiliary,
-							),
-							DiagnosticLabel::new(
-								"...but is unclosed at this point.",
-								iter.next().map(|t| t.span.start.clone()..t.span.start),
-								DiagnosticLabelPriority::Primary,
-							),
-						],
+						suggestions: vec![Suggestion::new(
+							"Close the list.",
+							end.clone()..end,
+							")",
+							Applicability::MachineApplicable,
+						)],
+						..Diagnostic::new(
+							DiagnosticType::UnclosedList,
+							vec![
+								DiagnosticLabel::new(
+									"This list starts here...",
+									paren_span,
+									DiagnosticLabelPriority::Auxiliary,
+								),
+								DiagnosticLabel::new(
+									"...but is unclosed at this point.",
+									iter.next().map(|t| t.span.start.clone()..t.span.start),
+									DiagnosticLabelPriority::Primary,
+								),
+							],
+						)
 					});
 					return Err(());
 				}
@@ -1349,40 +1719,56 @@ fn parse_value<'a, P: Position>(
 			}
 
 			// Errors
-			(lexerToken::InvalidZeroPrefixedDecimal(_), span) => {
+			(lexerToken::InvalidZeroPrefixedDecimal(str), span) => {
 				reporter.report_with(|| Diagnostic {
-					type_: DiagnosticType::ZeroPrefixedDecimalFound,
-					labels: vec![
-						DiagnosticLabel::new::<&'static str, _, _>(
-							None,
-							span,
-							DiagnosticLabelPriority::Primary,
-						),
-						DiagnosticLabel::new::<&'static str, _, _>(
-							"TAML does not support optional zero prefixes on numbers, as they could be confused with octal literals.",
-							None,
-							DiagnosticLabelPriority::Auxiliary,
-						),
-					],
+					suggestions: vec![Suggestion::new(
+						"Remove the zero prefix.",
+						span.clone(),
+						strip_zero_prefix(str),
+						Applicability::MachineApplicable,
+					)],
+					..Diagnostic::new(
+						DiagnosticType::ZeroPrefixedDecimalFound,
+						vec![
+							DiagnosticLabel::new::<&'static str, _, _>(
+								None,
+								span,
+								DiagnosticLabelPriority::Primary,
+							),
+							DiagnosticLabel::new::<&'static str, _, _>(
+								"TAML does not support optional zero prefixes on numbers, as they could be confused with octal literals.",
+								None,
+								DiagnosticLabelPriority::Auxiliary,
+							),
+						],
+					)
 				});
 				return Err(());
 			}
 
-			(lexerToken::InvalidZeroPrefixedInteger(_), span) => {
+			(lexerToken::InvalidZeroPrefixedInteger(str), span) => {
 				reporter.report_with(|| Diagnostic {
-					type_: DiagnosticType::ZeroPrefixedIntegerFound,
-					labels: vec![
-						DiagnosticLabel::new::<&'static str, _, _>(
-							None,
-							span,
-							DiagnosticLabelPriority::Primary,
-						),
-						DiagnosticLabel::new::<&'static str, _, _>(
-							"TAML does not support optional zero prefixes on numbers, as they could be confused with octal literals.",
-							None,
-							DiagnosticLabelPriority::Auxiliary,
-						),
-					],
+					suggestions: vec![Suggestion::new(
+						"Remove the zero prefix.",
+						span.clone(),
+						strip_zero_prefix(str),
+						Applicability::MachineApplicable,
+					)],
+					..Diagnostic::new(
+						DiagnosticType::ZeroPrefixedIntegerFound,
+						vec![
+							DiagnosticLabel::new::<&'static str, _, _>(
+								None,
+								span,
+								DiagnosticLabelPriority::Primary,
+							),
+							DiagnosticLabel::new::<&'static str, _, _>(
+								"TAML does not support optional zero prefixes on numbers, as they could be confused with octal literals.",
+								None,
+								DiagnosticLabelPriority::Auxiliary,
+							),
+						],
+					)
 				});
 				return Err(());
 			}
@@ -1392,41 +1778,34 @@ fn parse_value<'a, P: Position>(
 				_span,
 			) => {
 				if invalid_data_literal.encoding.contains('\r') {
-					reporter.report_with(|| Diagnostic {
-						type_: DiagnosticType::VerbatimCarriageReturnInsideLiteral,
-						labels: cr_labels(
+					reporter.report_with(|| {
+						cr_diagnostic(
 							invalid_data_literal.encoding,
 							invalid_data_literal.encoding_span.clone(),
-							Some('`'),
-						),
+							// The encoding already includes the backticks.
+							None,
+						)
 					});
 				}
 				if invalid_data_literal.unencoded_data.contains('\r') {
-					reporter.report_with(|| Diagnostic {
-						type_: DiagnosticType::VerbatimCarriageReturnInsideLiteral,
-						labels: cr_labels(
+					reporter.report_with(|| {
+						cr_diagnostic(
 							invalid_data_literal.unencoded_data,
 							invalid_data_literal.unencoded_data_span,
 							None,
-						),
+						)
 					});
 				}
 				return Err(());
 			}
 
 			(lexerToken::InvalidIdentifierWithVerbatimCarriageReturn(str), span) => {
-				reporter.report_with(|| Diagnostic {
-					type_: DiagnosticType::VerbatimCarriageReturnInsideLiteral,
-					labels: cr_labels(str, span, Some('`')),
-				});
+				reporter.report_with(|| cr_diagnostic(str, span, Some('`')));
 				return Err(());
 			}
 
 			(lexerToken::InvalidStringWithVerbatimCarriageReturn(str), span) => {
-				reporter.report_with(|| Diagnostic {
-					type_: DiagnosticType::VerbatimCarriageReturnInsideLiteral,
-					labels: cr_labels(str, span, Some('"')),
-				});
+				reporter.report_with(|| cr_diagnostic(str, span, Some('"')));
 				return Err(());
 			}
 
@@ -1437,31 +1816,281 @@ fn parse_value<'a, P: Position>(
 	}
 }
 
-fn cr_labels<P: Position>(
-	str: &str,
-	span: Range<P>,
-	delimiter: Option<char>,
-) -> Vec<DiagnosticLabel<P>> {
+/// Removes leading zeros from `number`, but keeps one before the decimal point if necessary.
+fn strip_zero_prefix(number: &str) -> std::string::String {
+	let (sign, digits) = number
+		.strip_prefix('-')
+		.map_or(("", number), |digits| ("-", digits));
+	let digits = digits.trim_start_matches('0');
+	if digits.is_empty() || digits.starts_with('.') {
+		format!("{}0{}", sign, digits)
+	} else {
+		format!("{}{}", sign, digits)
+	}
+}
+
+fn cr_diagnostic<P: Position>(str: &str, span: Range<P>, delimiter: Option<char>) -> Diagnostic<P> {
 	let delimiter_len = delimiter.map_or(0, char::len_utf8);
-	str.char_indices()
-		.filter_map(|(i, c)| (c == '\r').then(|| i))
-		.map(|i| DiagnosticLabel {
-			caption: None,
-			span: span
-				.start
-				.offset_range(delimiter_len + i..delimiter_len + i + '\r'.len_utf8()),
-			priority: DiagnosticLabelPriority::Primary,
+	let cr_spans = str
+		.match_indices('\r')
+		.map(|(i, cr)| {
+			span.start
+				.offset_range(delimiter_len + i..delimiter_len + i + cr.len())
 		})
-		.chain(iter::once(DiagnosticLabel::new(
-			"Hint: Either delete these code points or escape them as `\\r`.",
-			None,
-			DiagnosticLabelPriority::Auxiliary,
-		)))
-		// TODO
-		// .chain(iter::once(DiagnosticLabel::new(
-		// 	"Hint: `taml fix --erase-cr <file>` or `taml fix --escape-cr  <file>` will do this for you.",
-		// 	None,
-		// 	DiagnosticLabelPriority::Auxiliary,
-		// )))
-		.collect()
+		.collect::<Vec<_>>();
+	Diagnostic {
+		suggestions: cr_spans
+			.iter()
+			.flatten()
+			.cloned()
+			.map(|span| {
+				Suggestion::new(
+					"Escape the carriage return.",
+					span,
+					"\\r",
+					Applicability::MachineApplicable,
+				)
+			})
+			.collect(),
+		..Diagnostic::new(
+			DiagnosticType::VerbatimCarriageReturnInsideLiteral,
+			cr_spans
+				.into_iter()
+				.map(|span| DiagnosticLabel {
+					caption: None,
+					span,
+					priority: DiagnosticLabelPriority::Primary,
+				})
+				.chain(iter::once(DiagnosticLabel::new(
+					"Hint: Either delete these code points or escape them as `\\r`.",
+					None,
+					DiagnosticLabelPriority::Auxiliary,
+				)))
+				.collect(),
+		)
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn recovering() {
+	use crate::writing;
+	use logos::Logos as _;
+
+	let source = "a: 1\nb:\nc: ? 2 ?\n# x.\nd: 3\n## e\nf: 4\n# y\ng: 5\ng: 6\nh: (7\ni: 8\n";
+	let mut diagnostics = vec![];
+	let (map, had_errors) = parse_with(
+		lexerToken::lexer(source).spanned(),
+		&mut diagnostics,
+		ParseMode::Recovering,
+	);
+	let map = map.unwrap();
+	assert!(had_errors);
+	assert_eq!(
+		diagnostics
+			.iter()
+			.map(|diagnostic| diagnostic.type_)
+			.collect::<Vec<_>>(),
+		[
+			DiagnosticType::ExpectedValue,
+			DiagnosticType::ExpectedValue,
+			DiagnosticType::UnrecognizedToken,
+			DiagnosticType::ExpectedPathSegment,
+			DiagnosticType::KeyPreviouslyDefined,
+			DiagnosticType::UnclosedList,
+		]
+	);
+	assert_eq!(map.keys().collect::<Vec<_>>(), ["a", "b", "c", "y"]);
+	assert!(matches!(map["b"].value, TamlValue::Error));
+	assert_eq!(map["b"].span, 7..7);
+	match &map["y"].value {
+		TamlValue::Map(y) => {
+			assert_eq!(y.keys().collect::<Vec<_>>(), ["g", "h", "i"]);
+			assert!(matches!(y["h"].value, TamlValue::Error));
+		}
+		_ => panic!("Expected map."),
+	}
+	assert_eq!(
+		writing::to_string(&map),
+		Err(writing::Error::ErrorValue(7..7))
+	);
+
+	let (map, had_errors) = parse_with(
+		lexerToken::lexer(source).spanned(),
+		&mut (),
+		ParseMode::Strict,
+	);
+	assert!(map.is_none() && had_errors);
+}
+
+#[cfg(test)]
+#[test]
+fn conflicts() {
+	use logos::Logos as _;
+	use DiagnosticLabelPriority::{Auxiliary, Primary};
+
+	let diagnose = |source| {
+		let mut diagnostics = vec![];
+		assert!(parse(lexerToken::lexer(source).spanned(), &mut diagnostics).is_err());
+		assert_eq!(diagnostics.len(), 1);
+		let diagnostic = diagnostics.pop().unwrap();
+		(
+			diagnostic.type_,
+			diagnostic
+				.labels
+				.into_iter()
+				.map(|label| (label.span.unwrap(), label.priority))
+				.collect::<Vec<_>>(),
+		)
+	};
+
+	assert_eq!(
+		diagnose("a: 1\na: 2\n"),
+		(
+			DiagnosticType::KeyPreviouslyDefined,
+			vec![(0..1, Auxiliary), (5..6, Primary)]
+		)
+	);
+	assert_eq!(
+		diagnose("a: 1\n# [a]\n"),
+		(
+			DiagnosticType::NonListValueSelected,
+			vec![(3..4, Auxiliary), (7..10, Primary)]
+		)
+	);
+	assert_eq!(
+		diagnose("a: 1\n# [[a]]\n"),
+		(
+			DiagnosticType::NonListValueSelected,
+			vec![(3..4, Auxiliary), (8..11, Primary)]
+		)
+	);
+	assert_eq!(
+		diagnose("# [[a].{d, d}]\n1, 2\n"),
+		(
+			DiagnosticType::KeyPreviouslyDefined,
+			vec![(8..9, Auxiliary), (11..12, Primary)]
+		)
+	);
+	assert_eq!(
+		diagnose("# [[a].b:V]\n1\n"),
+		(
+			DiagnosticType::TupleVariantValueNotAList,
+			vec![(9..10, Auxiliary), (12..13, Primary)]
+		)
+	);
+	// Short rows are rejected before any of their values are assigned.
+	assert_eq!(
+		diagnose("# [[a].{b, c}]\n1\n"),
+		(DiagnosticType::ValuesLineTooShort, vec![(16..16, Primary)])
+	);
+}
+
+#[cfg(test)]
+#[test]
+fn fixes() {
+	use crate::diagnostics::apply_fixes;
+	use logos::Logos as _;
+
+	let source = "a: 007\nb: -00.50\nc: (1, 2,\nd: (3 // x\ne: \"x\ry\r\"\n# f:\ng: 4\n";
+	let mut diagnostics = vec![];
+	let (_, had_errors) = parse_with(
+		lexerToken::lexer(source).spanned(),
+		&mut diagnostics,
+		ParseMode::Recovering,
+	);
+	assert!(had_errors);
+	assert_eq!(
+		diagnostics.last().unwrap().suggestions,
+		[Suggestion::new(
+			"Remove the colon.",
+			source.find("f:").unwrap() + 1..source.find("f:").unwrap() + 2,
+			"",
+			Applicability::MaybeIncorrect,
+		)]
+	);
+
+	let fixed = apply_fixes(source, &diagnostics);
+	assert_eq!(
+		fixed,
+		"a: 7\nb: -0.5\nc: (1, 2,)\nd: (3) // x\ne: \"x\\ry\\r\"\n# f:\ng: 4\n"
+	);
+	let mut diagnostics = vec![];
+	parse(
+		lexerToken::lexer(&fixed[..fixed.find("# f:").unwrap()]).spanned(),
+		&mut diagnostics,
+	)
+	.unwrap();
+	assert_eq!(diagnostics, []);
+}
+
+#[cfg(test)]
+#[test]
+fn suppression() {
+	use logos::Logos as _;
+
+	let source = "a: 1\na: 2 // taml-allow: P0013\n// taml-allow: L0002, TAML-P0013\na: 3\na: 4\nb: 05 // taml-allow: P0013\n";
+	let mut diagnostics = vec![];
+	let (_, had_errors) = parse_with(
+		lexerToken::lexer(source).spanned(),
+		&mut diagnostics,
+		ParseMode::Recovering,
+	);
+	assert!(had_errors);
+	assert_eq!(
+		diagnostics
+			.iter()
+			.map(|diagnostic| (
+				diagnostic.type_,
+				diagnostic
+					.labels
+					.iter()
+					.find(|label| label.priority == DiagnosticLabelPriority::Primary)
+					.and_then(|label| label.span.clone())
+					.unwrap()
+			))
+			.collect::<Vec<_>>(),
+		[
+			(
+				DiagnosticType::KeyPreviouslyDefined,
+				source.find("a: 4").unwrap()..source.find("a: 4").unwrap() + 1
+			),
+			(
+				DiagnosticType::ZeroPrefixedIntegerFound,
+				source.find("05").unwrap()..source.find("05").unwrap() + 2
+			),
+		]
+	);
+}
+
+#[cfg(test)]
+#[test]
+fn suppression_by_code() {
+	use logos::Logos as _;
+
+	// TAML-P0015 is shared with `UnclosedTabularPathSection`.
+	let source = "# a:V\nb: 1\n\n# a:V // taml-allow: P0015\nc: 2\n";
+	let mut diagnostics = vec![];
+	parse_with(
+		lexerToken::lexer(source).spanned(),
+		&mut diagnostics,
+		ParseMode::Recovering,
+	);
+	assert_eq!(diagnostics, []);
+
+	// Without location, a suppression can't be matched to a line.
+	let source = "a: 1 // taml-allow: P0013\na: 2\n";
+	let mut diagnostics: Vec<Diagnostic<()>> = vec![];
+	parse_with(
+		lexerToken::lexer(source).map(|token| (token.map_position(|_| ()), ()..())),
+		&mut diagnostics,
+		ParseMode::Recovering,
+	);
+	assert_eq!(
+		diagnostics
+			.iter()
+			.map(|diagnostic| diagnostic.type_)
+			.collect::<Vec<_>>(),
+		[DiagnosticType::KeyPreviouslyDefined]
+	);
 }
@@ -0,0 +1,76 @@
+//! The value path syntax used by document editing and lookups, like `a.b[2].c`.
+//!
+//! Keys are written as TAML identifiers (quoted with backticks if necessary)
+//! and list indices as `[n]`. Keys are separated by periods.
+
+use smartstring::alias::String;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum PathPart {
+	Key(String),
+	Index(usize),
+}
+
+/// Parses `path` into its parts.
+///
+/// # Errors
+///
+/// Iff `path` is empty or malformed.
+pub(crate) fn parse_path(path: &str) -> Result<Vec<PathPart>, ()> {
+	let mut parts = vec![];
+	let mut chars = path.chars().peekable();
+	loop {
+		match chars.peek() {
+			Some('[') => {
+				chars.next();
+				let mut digits = String::new();
+				while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+					digits.push(digit)
+				}
+				if chars.next() != Some(']') {
+					return Err(());
+				}
+				parts.push(PathPart::Index(digits.parse().map_err(|_| ())?))
+			}
+			Some('`') => {
+				chars.next();
+				let mut key = String::new();
+				loop {
+					match chars.next().ok_or(())? {
+						'`' => break,
+						'\\' => match chars.next().ok_or(())? {
+							c @ ('\\' | '`') => key.push(c),
+							'r' => key.push('\r'),
+							_ => return Err(()),
+						},
+						c => key.push(c),
+					}
+				}
+				parts.push(PathPart::Key(key))
+			}
+			Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
+				let mut key = String::new();
+				while let Some(c) =
+					chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+				{
+					key.push(c)
+				}
+				parts.push(PathPart::Key(key))
+			}
+			_ => return Err(()),
+		}
+
+		match chars.peek() {
+			None => return Ok(parts),
+			// Indices follow immediately.
+			Some('[') => (),
+			Some('.') => {
+				chars.next();
+				if chars.peek() == Some(&'[') {
+					return Err(());
+				}
+			}
+			Some(_) => return Err(()),
+		}
+	}
+}
@@ -0,0 +1,825 @@
+//! Serialises any [`Serialize`] type as TAML text.
+//!
+//! Requires the `"serde"` feature.
+//!
+//! Values are collected into a [`Map`] and then written by the [`writing`] module,
+//! so the output is canonical and accepted by [`parse`](`crate::parse`):
+//!
+//! - Structs and maps become sections or key-value pairs, and `None` fields are left out.
+//! - Lists of structs become `# [list]` or tabular sections, depending on the [`WriteOptions`].
+//! - Enum variants become TAML enum variants, with newtype variants written like `Variant(value)`.
+//! - `true` and `false` become the unit variants of the same name, and units become empty lists.
+//! - Byte buffers become `<base64:…>` data literals.
+
+use crate::{
+	encoding::Base64,
+	parsing::{Key, Map, Taml, TamlValue, VariantPayload},
+	writing::{self, WriteOptions},
+	DataLiteral,
+};
+use cervine::Cow;
+use serde::{
+	ser::{self, Impossible},
+	Serialize,
+};
+use std::fmt::{self, Display, Formatter};
+
+/// Reasons why a value could not be serialised.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+	/// A custom error from a [`Serialize`] implementation.
+	Custom(String),
+	/// The top-level value is not a struct or map.
+	NotAMap,
+	/// A map key is not a string, character or integer.
+	KeyMustBeAString,
+	/// `None` appeared outside of a struct or map field, where it can't be left out.
+	UnsupportedNone,
+	/// A floating point number is infinite or NaN, which TAML can't represent.
+	NonFiniteFloat,
+	/// The collected structure could not be written.
+	Write(writing::Error<()>),
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::Custom(message) => f.write_str(message),
+			Error::NotAMap => {
+				f.write_str("Only structs and maps can be serialised as TAML document.")
+			}
+			Error::KeyMustBeAString => {
+				f.write_str("Map keys must be strings, characters or integers.")
+			}
+			Error::UnsupportedNone => {
+				f.write_str("`None` can only be serialised as struct or map field.")
+			}
+			Error::NonFiniteFloat => f.write_str("Infinite and NaN floats can't be serialised."),
+			Error::Write(error) => write!(f, "Failed to write TAML: {:?}", error),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+	fn custom<T: Display>(msg: T) -> Self {
+		Error::Custom(msg.to_string())
+	}
+}
+
+impl From<writing::Error<()>> for Error {
+	fn from(error: writing::Error<()>) -> Self {
+		Error::Write(error)
+	}
+}
+
+/// Serialises `value` as TAML document into a new [`String`], using the default [`WriteOptions`].
+///
+/// # Errors
+///
+/// Iff `value` is not a struct or map, or contains data that can't be represented in TAML.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+	to_string_with(value, &WriteOptions::default())
+}
+
+/// Serialises `value` as TAML document into a new [`String`].
+///
+/// # Errors
+///
+/// Iff `value` is not a struct or map, or contains data that can't be represented in TAML.
+pub fn to_string_with<T: Serialize + ?Sized>(
+	value: &T,
+	options: &WriteOptions,
+) -> Result<String, Error> {
+	let entries = match value.serialize(Serializer)? {
+		Value::Map(entries) => entries,
+		_ => return Err(Error::NotAMap),
+	};
+	Ok(writing::to_string_with(&to_map(&entries)?, options)?)
+}
+
+/// An owned intermediate representation, which is borrowed as [`Taml`] for writing.
+enum Value {
+	None,
+	String(String),
+	Base64(String),
+	Integer(String),
+	Decimal(String),
+	List(Vec<Value>),
+	Map(Vec<(String, Value)>),
+	Variant { key: &'static str, payload: Payload },
+}
+
+enum Payload {
+	Unit,
+	Tuple(Vec<Value>),
+	Structured(Vec<(String, Value)>),
+}
+
+fn to_map(entries: &[(String, Value)]) -> Result<Map<'_, ()>, Error> {
+	entries
+		.iter()
+		.filter(|(_, value)| !matches!(value, Value::None))
+		.map(|(key, value)| Ok((to_key(key), to_taml(value)?)))
+		.collect()
+}
+
+fn to_list(items: &[Value]) -> Result<Vec<Taml<'_, ()>>, Error> {
+	items.iter().map(to_taml).collect()
+}
+
+fn to_key(name: &str) -> Key<'_, ()> {
+	Key {
+		name: Cow::Borrowed(name),
+		span: ()..(),
+	}
+}
+
+fn to_taml(value: &Value) -> Result<Taml<'_, ()>, Error> {
+	let value = match value {
+		Value::None => return Err(Error::UnsupportedNone),
+		Value::String(string) => TamlValue::String(Cow::Borrowed(string)),
+		Value::Base64(encoded) => TamlValue::DataLiteral(DataLiteral {
+			encoding: Cow::Borrowed("base64"),
+			encoding_span: ()..(),
+			unencoded_data: Cow::Borrowed(encoded),
+			unencoded_data_span: ()..(),
+		}),
+		Value::Integer(str) => TamlValue::Integer(str),
+		Value::Decimal(str) => TamlValue::Decimal(str),
+		Value::List(items) => TamlValue::List(to_list(items)?),
+		Value::Map(entries) => TamlValue::Map(to_map(entries)?),
+		Value::Variant { key, payload } => TamlValue::EnumVariant {
+			key: to_key(key),
+			payload: match payload {
+				Payload::Unit => VariantPayload::Unit,
+				Payload::Tuple(items) => VariantPayload::Tuple(to_list(items)?),
+				Payload::Structured(entries) => VariantPayload::Structured(to_map(entries)?),
+			},
+		},
+	};
+	Ok(Taml {
+		value,
+		span: ()..(),
+	})
+}
+
+/// Turns the [`Display`] output of a finite float into a TAML decimal.
+///
+/// [`Display`] never uses exponents, but leaves out the fractional part of whole numbers.
+fn decimal(mut string: String) -> Value {
+	if !string.contains('.') {
+		string.push_str(".0");
+	}
+	Value::Decimal(string)
+}
+
+fn unit_variant(key: &'static str) -> Value {
+	Value::Variant {
+		key,
+		payload: Payload::Unit,
+	}
+}
+
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+	type Ok = Value;
+	type Error = Error;
+	type SerializeSeq = SerializeList;
+	type SerializeTuple = SerializeList;
+	type SerializeTupleStruct = SerializeList;
+	type SerializeTupleVariant = SerializeList;
+	type SerializeMap = SerializeMap;
+	type SerializeStruct = SerializeMap;
+	type SerializeStructVariant = SerializeMap;
+
+	fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+		Ok(unit_variant(if v { "true" } else { "false" }))
+	}
+
+	fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+		self.serialize_i128(v.into())
+	}
+
+	fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+		self.serialize_i128(v.into())
+	}
+
+	fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+		self.serialize_i128(v.into())
+	}
+
+	fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+		self.serialize_i128(v.into())
+	}
+
+	fn serialize_i128(self, v: i128) -> Result<Value, Error> {
+		Ok(Value::Integer(v.to_string()))
+	}
+
+	fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+		self.serialize_u128(v.into())
+	}
+
+	fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+		self.serialize_u128(v.into())
+	}
+
+	fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+		self.serialize_u128(v.into())
+	}
+
+	fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+		self.serialize_u128(v.into())
+	}
+
+	fn serialize_u128(self, v: u128) -> Result<Value, Error> {
+		Ok(Value::Integer(v.to_string()))
+	}
+
+	fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+		// Not widened to `f64`, as that would make the shortest representation longer.
+		v.is_finite()
+			.then(|| decimal(v.to_string()))
+			.ok_or(Error::NonFiniteFloat)
+	}
+
+	fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+		v.is_finite()
+			.then(|| decimal(v.to_string()))
+			.ok_or(Error::NonFiniteFloat)
+	}
+
+	fn serialize_char(self, v: char) -> Result<Value, Error> {
+		Ok(Value::String(v.into()))
+	}
+
+	fn serialize_str(self, v: &str) -> Result<Value, Error> {
+		Ok(Value::String(v.into()))
+	}
+
+	fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+		Ok(Value::Base64(Base64::encode(v)))
+	}
+
+	fn serialize_none(self) -> Result<Value, Error> {
+		Ok(Value::None)
+	}
+
+	fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<Value, Error> {
+		Ok(Value::List(vec![]))
+	}
+
+	fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+		self.serialize_unit()
+	}
+
+	fn serialize_unit_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		variant: &'static str,
+	) -> Result<Value, Error> {
+		Ok(unit_variant(variant))
+	}
+
+	fn serialize_newtype_struct<T: Serialize + ?Sized>(
+		self,
+		_name: &'static str,
+		value: &T,
+	) -> Result<Value, Error> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: Serialize + ?Sized>(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		variant: &'static str,
+		value: &T,
+	) -> Result<Value, Error> {
+		let mut list = SerializeList::new(Some(variant));
+		ser::SerializeSeq::serialize_element(&mut list, value)?;
+		ser::SerializeSeq::end(list)
+	}
+
+	fn serialize_seq(self, _len: Option<usize>) -> Result<SerializeList, Error> {
+		Ok(SerializeList::new(None))
+	}
+
+	fn serialize_tuple(self, _len: usize) -> Result<SerializeList, Error> {
+		Ok(SerializeList::new(None))
+	}
+
+	fn serialize_tuple_struct(
+		self,
+		_name: &'static str,
+		_len: usize,
+	) -> Result<SerializeList, Error> {
+		Ok(SerializeList::new(None))
+	}
+
+	fn serialize_tuple_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		variant: &'static str,
+		_len: usize,
+	) -> Result<SerializeList, Error> {
+		Ok(SerializeList::new(Some(variant)))
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<SerializeMap, Error> {
+		Ok(SerializeMap::new(None))
+	}
+
+	fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<SerializeMap, Error> {
+		Ok(SerializeMap::new(None))
+	}
+
+	fn serialize_struct_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		variant: &'static str,
+		_len: usize,
+	) -> Result<SerializeMap, Error> {
+		Ok(SerializeMap::new(Some(variant)))
+	}
+}
+
+/// Collects lists and tuple variants.
+struct SerializeList {
+	items: Vec<Value>,
+	variant: Option<&'static str>,
+}
+
+impl SerializeList {
+	fn new(variant: Option<&'static str>) -> Self {
+		Self {
+			items: vec![],
+			variant,
+		}
+	}
+}
+
+impl ser::SerializeSeq for SerializeList {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		match value.serialize(Serializer)? {
+			Value::None => Err(Error::UnsupportedNone),
+			value => {
+				self.items.push(value);
+				Ok(())
+			}
+		}
+	}
+
+	fn end(self) -> Result<Value, Error> {
+		Ok(match self.variant {
+			Some(key) => Value::Variant {
+				key,
+				payload: Payload::Tuple(self.items),
+			},
+			None => Value::List(self.items),
+		})
+	}
+}
+
+impl ser::SerializeTuple for SerializeList {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		ser::SerializeSeq::serialize_element(self, value)
+	}
+
+	fn end(self) -> Result<Value, Error> {
+		ser::SerializeSeq::end(self)
+	}
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		ser::SerializeSeq::serialize_element(self, value)
+	}
+
+	fn end(self) -> Result<Value, Error> {
+		ser::SerializeSeq::end(self)
+	}
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		ser::SerializeSeq::serialize_element(self, value)
+	}
+
+	fn end(self) -> Result<Value, Error> {
+		ser::SerializeSeq::end(self)
+	}
+}
+
+/// Collects maps, structs and struct variants.
+struct SerializeMap {
+	entries: Vec<(String, Value)>,
+	next_key: Option<String>,
+	variant: Option<&'static str>,
+}
+
+impl SerializeMap {
+	fn new(variant: Option<&'static str>) -> Self {
+		Self {
+			entries: vec![],
+			next_key: None,
+			variant,
+		}
+	}
+}
+
+impl ser::SerializeMap for SerializeMap {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+		self.next_key = Some(key.serialize(KeySerializer)?);
+		Ok(())
+	}
+
+	fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		let key = self
+			.next_key
+			.take()
+			.expect("`serialize_value` called before `serialize_key`.");
+		self.entries.push((key, value.serialize(Serializer)?));
+		Ok(())
+	}
+
+	fn end(self) -> Result<Value, Error> {
+		Ok(match self.variant {
+			Some(key) => Value::Variant {
+				key,
+				payload: Payload::Structured(self.entries),
+			},
+			None => Value::Map(self.entries),
+		})
+	}
+}
+
+impl ser::SerializeStruct for SerializeMap {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(
+		&mut self,
+		key: &'static str,
+		value: &T,
+	) -> Result<(), Error> {
+		self.entries
+			.push((key.to_string(), value.serialize(Serializer)?));
+		Ok(())
+	}
+
+	fn end(self) -> Result<Value, Error> {
+		ser::SerializeMap::end(self)
+	}
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(
+		&mut self,
+		key: &'static str,
+		value: &T,
+	) -> Result<(), Error> {
+		ser::SerializeStruct::serialize_field(self, key, value)
+	}
+
+	fn end(self) -> Result<Value, Error> {
+		ser::SerializeMap::end(self)
+	}
+}
+
+/// Serialises map keys, which must be strings, characters or integers.
+struct KeySerializer;
+
+impl ser::Serializer for KeySerializer {
+	type Ok = String;
+	type Error = Error;
+	type SerializeSeq = Impossible<String, Error>;
+	type SerializeTuple = Impossible<String, Error>;
+	type SerializeTupleStruct = Impossible<String, Error>;
+	type SerializeTupleVariant = Impossible<String, Error>;
+	type SerializeMap = Impossible<String, Error>;
+	type SerializeStruct = Impossible<String, Error>;
+	type SerializeStructVariant = Impossible<String, Error>;
+
+	fn serialize_bool(self, _v: bool) -> Result<String, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_i8(self, v: i8) -> Result<String, Error> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_i16(self, v: i16) -> Result<String, Error> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_i32(self, v: i32) -> Result<String, Error> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_i64(self, v: i64) -> Result<String, Error> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_i128(self, v: i128) -> Result<String, Error> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_u8(self, v: u8) -> Result<String, Error> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_u16(self, v: u16) -> Result<String, Error> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_u32(self, v: u32) -> Result<String, Error> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_u64(self, v: u64) -> Result<String, Error> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_u128(self, v: u128) -> Result<String, Error> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_f32(self, _v: f32) -> Result<String, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_f64(self, _v: f64) -> Result<String, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_char(self, v: char) -> Result<String, Error> {
+		Ok(v.into())
+	}
+
+	fn serialize_str(self, v: &str) -> Result<String, Error> {
+		Ok(v.into())
+	}
+
+	fn serialize_bytes(self, _v: &[u8]) -> Result<String, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_none(self) -> Result<String, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<String, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_unit(self) -> Result<String, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_unit_struct(self, _name: &'static str) -> Result<String, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_unit_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		variant: &'static str,
+	) -> Result<String, Error> {
+		Ok(variant.into())
+	}
+
+	fn serialize_newtype_struct<T: Serialize + ?Sized>(
+		self,
+		_name: &'static str,
+		value: &T,
+	) -> Result<String, Error> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: Serialize + ?Sized>(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		_variant: &'static str,
+		_value: &T,
+	) -> Result<String, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_tuple_struct(
+		self,
+		_name: &'static str,
+		_len: usize,
+	) -> Result<Self::SerializeTupleStruct, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_tuple_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		_variant: &'static str,
+		_len: usize,
+	) -> Result<Self::SerializeTupleVariant, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_struct(
+		self,
+		_name: &'static str,
+		_len: usize,
+	) -> Result<Self::SerializeStruct, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+
+	fn serialize_struct_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		_variant: &'static str,
+		_len: usize,
+	) -> Result<Self::SerializeStructVariant, Error> {
+		Err(Error::KeyMustBeAString)
+	}
+}
+
+#[cfg(test)]
+#[test]
+#[allow(clippy::too_many_lines)]
+fn serialize() {
+	use serde::{Deserialize, Serialize};
+	use std::collections::BTreeMap;
+
+	#[derive(Debug, Serialize, Deserialize, PartialEq)]
+	enum Mode {
+		Fast,
+		Limited(u8),
+		Range(i8, i8),
+		Custom { name: String },
+	}
+
+	#[derive(Debug, Serialize, Deserialize, PartialEq)]
+	struct Server {
+		host: String,
+		port: u16,
+	}
+
+	#[derive(Debug, Serialize, Deserialize, PartialEq)]
+	struct Plugin {
+		name: String,
+		mode: Mode,
+	}
+
+	#[derive(Debug, Serialize, Deserialize, PartialEq)]
+	struct Config {
+		name: String,
+		ratio: f64,
+		whole: f32,
+		enabled: bool,
+		timeout: Option<u32>,
+		retries: Option<u32>,
+		modes: Vec<Mode>,
+		key: serde_bytes_shim::Bytes,
+		servers: Vec<Server>,
+		plugins: Vec<Plugin>,
+		labels: BTreeMap<String, String>,
+		default_mode: Mode,
+	}
+
+	/// Serialises as byte buffer, like `serde_bytes` does.
+	mod serde_bytes_shim {
+		#[derive(Debug, PartialEq)]
+		pub struct Bytes(pub Vec<u8>);
+
+		impl serde::Serialize for Bytes {
+			fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+				serializer.serialize_bytes(&self.0)
+			}
+		}
+
+		impl<'de> serde::Deserialize<'de> for Bytes {
+			fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+				Vec::deserialize(deserializer).map(Self)
+			}
+		}
+	}
+
+	let config = Config {
+		name: "example \"config\"".to_string(),
+		ratio: 4.0,
+		whole: 0.1,
+		enabled: true,
+		timeout: Some(30),
+		retries: None,
+		modes: vec![Mode::Fast, Mode::Limited(5), Mode::Range(-1, 1)],
+		key: serde_bytes_shim::Bytes(b"key>".to_vec()),
+		servers: vec![
+			Server {
+				host: "a".to_string(),
+				port: 1,
+			},
+			Server {
+				host: "b".to_string(),
+				port: 2,
+			},
+		],
+		plugins: vec![Plugin {
+			name: "p".to_string(),
+			mode: Mode::Fast,
+		}],
+		labels: vec![("first key".to_string(), "1".to_string())]
+			.into_iter()
+			.collect(),
+		default_mode: Mode::Custom {
+			name: "c".to_string(),
+		},
+	};
+
+	let text = to_string(&config).unwrap();
+	assert_eq!(
+		text,
+		r#"name: "example \"config\""
+ratio: 4.0
+whole: 0.1
+enabled: true
+timeout: 30
+modes: (Fast, Limited(5), Range(-1, 1))
+key: <base64:a2V5Pg==>
+
+# [[servers].{host, port}]
+"a", 1
+"b", 2
+
+# [plugins]
+name: "p"
+mode: Fast
+
+# labels
+`first key`: "1"
+
+# default_mode: Custom
+name: "c"
+"#
+	);
+
+	let mut diagnostics = vec![];
+	let deserialized: Config = crate::from_str(&text, &mut diagnostics).unwrap();
+	assert_eq!(diagnostics, []);
+	assert_eq!(deserialized, config);
+
+	assert_eq!(to_string(&1), Err(Error::NotAMap));
+	assert_eq!(
+		to_string(
+			&vec![("a", vec![None, Some(1)])]
+				.into_iter()
+				.collect::<BTreeMap<_, _>>()
+		),
+		Err(Error::UnsupportedNone)
+	);
+	assert_eq!(
+		to_string(&vec![((), 1)].into_iter().collect::<BTreeMap<_, _>>()),
+		Err(Error::KeyMustBeAString)
+	);
+}
@@ -0,0 +1,135 @@
+use crate::{LineColumn, LineIndex, Position, Token};
+use std::ops::Range;
+
+/// Identifies a source file within a [`SourceMap`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(usize);
+
+/// A [`Position`] that identifies the source file it refers to, in addition to a byte offset into it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FilePosition {
+	pub file: FileId,
+	pub offset: usize,
+}
+
+impl Position for FilePosition {
+	fn offset_range(&self, local_range: Range<usize>) -> Option<Range<Self>> {
+		Some(
+			Self {
+				file: self.file,
+				offset: self.offset + local_range.start,
+			}..Self {
+				file: self.file,
+				offset: self.offset + local_range.end,
+			},
+		)
+	}
+}
+
+/// A collection of named source texts, for example when merging several TAML files.
+///
+/// Lexing a file through [`SourceMap::lex`] yields [`FilePosition`]s,
+/// so all spans in the parsed structure and in diagnostics identify the file they refer to.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap<'a> {
+	files: Vec<(std::string::String, LineIndex<'a>)>,
+}
+
+impl<'a> SourceMap<'a> {
+	/// Creates an empty [`SourceMap`].
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds `source` under `name` (usually a path) and returns its new [`FileId`].
+	pub fn add(&mut self, name: impl Into<std::string::String>, source: &'a str) -> FileId {
+		self.files.push((name.into(), LineIndex::new(source)));
+		FileId(self.files.len() - 1)
+	}
+
+	/// The [`FileId`]s of all files in this map, in the order they were added.
+	pub fn files(&self) -> impl Iterator<Item = FileId> {
+		(0..self.files.len()).map(FileId)
+	}
+
+	/// The name `file` was added under.
+	///
+	/// # Panics
+	///
+	/// Iff `file` doesn't belong to this [`SourceMap`].
+	#[must_use]
+	pub fn name(&self, file: FileId) -> &str {
+		&self.files[file.0].0
+	}
+
+	/// The source text of `file`.
+	///
+	/// # Panics
+	///
+	/// Iff `file` doesn't belong to this [`SourceMap`].
+	#[must_use]
+	pub fn source(&self, file: FileId) -> &'a str {
+		self.line_index(file).source()
+	}
+
+	/// The [`LineIndex`] of `file`.
+	///
+	/// # Panics
+	///
+	/// Iff `file` doesn't belong to this [`SourceMap`].
+	#[must_use]
+	pub fn line_index(&self, file: FileId) -> &LineIndex<'a> {
+		&self.files[file.0].1
+	}
+
+	/// Converts `position` into a [`LineColumn`] within its file.
+	///
+	/// # Panics
+	///
+	/// Iff `position.file` doesn't belong to this [`SourceMap`],
+	/// or if `position.offset` is out of bounds or not on a [`char`] boundary.
+	#[must_use]
+	pub fn line_column(&self, position: FilePosition) -> LineColumn {
+		self.line_index(position.file).line_column(position.offset)
+	}
+
+	/// Lexes `file` with [`FilePosition`] positions, for use with [`parse`](`crate::parse`).
+	///
+	/// # Panics
+	///
+	/// Iff `file` doesn't belong to this [`SourceMap`].
+	pub fn lex(
+		&self,
+		file: FileId,
+	) -> impl Iterator<Item = (Token<'a, FilePosition>, Range<FilePosition>)> {
+		Token::lex(self.source(file), FilePosition { file, offset: 0 })
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn source_map() {
+	use crate::parse;
+
+	let mut source_map = SourceMap::new();
+	let first = source_map.add("first.taml", "a: 1\n");
+	let second = source_map.add("second.taml", "b: <hex:0f>\n");
+	assert_eq!(source_map.files().collect::<Vec<_>>(), [first, second]);
+	assert_eq!(source_map.name(second), "second.taml");
+
+	let map = parse(source_map.lex(second), &mut ()).unwrap();
+	let (key, value) = map.first().unwrap();
+	assert_eq!(
+		key.span,
+		FilePosition {
+			file: second,
+			offset: 0
+		}..FilePosition {
+			file: second,
+			offset: 1
+		}
+	);
+	assert_eq!(value.span.start.file, second);
+	assert_eq!(source_map.line_column(value.span.end).utf8_column, 11);
+}
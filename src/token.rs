@@ -1,4 +1,4 @@
-use crate::DataLiteral;
+use crate::{DataLiteral, Position};
 use cervine::Cow;
 use gnaw::Unshift as _;
 use lazy_transform_str::{Transform as _, TransformedPart};
@@ -22,6 +22,18 @@ pub struct InvalidDataLiteral<'a, Position> {
 	pub unencoded_data_span: Range<Position>,
 }
 
+impl<'a, Position> InvalidDataLiteral<'a, Position> {
+	/// Converts [`Self::encoding_span`] and [`Self::unencoded_data_span`] using `f`.
+	pub fn map_position<P>(self, mut f: impl FnMut(Position) -> P) -> InvalidDataLiteral<'a, P> {
+		InvalidDataLiteral {
+			encoding: self.encoding,
+			encoding_span: f(self.encoding_span.start)..f(self.encoding_span.end),
+			unencoded_data: self.unencoded_data,
+			unencoded_data_span: f(self.unencoded_data_span.start)..f(self.unencoded_data_span.end),
+		}
+	}
+}
+
 #[must_use = "pure function"]
 pub fn escape_unencoded_data(string: &str) -> Cow<String, str> {
 	string.transform(|rest| match rest.unshift().unwrap() {
@@ -37,7 +49,7 @@ pub fn escape_unencoded_data(string: &str) -> Cow<String, str> {
 
 macro_rules! define_escape {
 	($name:ident, delimiter = $delimiter:literal, always_quote = $always_quote:literal) => {
-		fn $name(string: &str) -> Cow<String, str> {
+		pub(crate) fn $name(string: &str) -> Cow<String, str> {
 			let mut quote = $always_quote
 				|| match string.chars().next() {
 					Some(first) => first == '-' || first.is_ascii_digit(),
@@ -58,7 +70,7 @@ macro_rules! define_escape {
 					if !(('a'..='z').contains(&c)
 						|| ('A'..='Z').contains(&c)
 						|| c == '-' || c == '_'
-						|| ('0'..'9').contains(&c))
+						|| ('0'..='9').contains(&c))
 					{
 						quote = true
 					}
@@ -111,7 +123,7 @@ fn trim_trailing_0s(mut s: &str) -> &str {
 #[derive(Logos, Debug, Clone, PartialEq, Eq)]
 #[logos(type Position = usize)]
 pub enum Token<'a, Position> {
-	#[regex(r"//[^\r\n]+", |lex| lex.slice()[2..].trim_end_matches([' ', '\t'].as_ref()))]
+	#[regex(r"//[^\r\n]*", |lex| lex.slice()[2..].trim_end_matches([' ', '\t'].as_ref()))]
 	Comment(&'a str),
 
 	#[regex("#+", |lex| lex.slice().chars().count())]
@@ -161,7 +173,7 @@ pub enum Token<'a, Position> {
 		let (encoding, unencoded_data) = lex.slice()['<'.len_utf8()..lex.slice().len() - '>'.len_utf8()].split_once(':').unwrap(); //FIXME: Broken if identifier contains `:`.
 		DataLiteral {
 			encoding: unescape_verbatim_and_r_to_carriage_return(&encoding['`'.len_utf8()..encoding.len()-'`'.len_utf8()]),
-			encoding_span: lex.span().start + '`'.len_utf8()..lex.span().start + '`'.len_utf8() + encoding.len(),
+			encoding_span: lex.span().start + '<'.len_utf8()..lex.span().start + '<'.len_utf8() + encoding.len(),
 			unencoded_data: unescape_verbatim_and_r_to_carriage_return(unencoded_data),
 			unencoded_data_span: lex.span().end - '>'.len_utf8() - unencoded_data.len()..lex.span().end - '>'.len_utf8(),
 		}
@@ -173,7 +185,7 @@ pub enum Token<'a, Position> {
 		let (encoding, unencoded_data) = lex.slice()[1..lex.slice().len() - 1].split_once(':').unwrap();
 		InvalidDataLiteral {
 			encoding,
-			encoding_span: lex.span().start + '`'.len_utf8()..lex.span().start + '`'.len_utf8() + encoding.len(),
+			encoding_span: lex.span().start + '<'.len_utf8()..lex.span().start + '<'.len_utf8() + encoding.len(),
 			unencoded_data,
 			unencoded_data_span: lex.span().end - '>'.len_utf8() - unencoded_data.len()..lex.span().end - '>'.len_utf8(),
 		}
@@ -208,6 +220,66 @@ pub enum Token<'a, Position> {
 	Error,
 }
 
+impl<'a, P: Position + 'a> Token<'a, P> {
+	/// Lexes `source` into tokens and their spans, where `origin` is the position of the start of `source`.
+	///
+	/// Positions, including the inner spans of data literals, are calculated via [`Position::offset_range`].
+	/// Where that returns [`None`], [`P::default()`](`Default::default`) is used instead,
+	/// so for [`LineColumn`](`crate::LineColumn`) positions, use [`LineIndex::lex`](`crate::LineIndex::lex`).
+	pub fn lex(source: &'a str, origin: P) -> impl Iterator<Item = (Self, Range<P>)> + 'a {
+		let position = move |offset| {
+			origin
+				.offset_range(offset..offset)
+				.map_or_else(P::default, |range| range.start)
+		};
+		Token::lexer(source).spanned().map(move |(token, span)| {
+			(
+				token.map_position(&position),
+				position(span.start)..position(span.end),
+			)
+		})
+	}
+}
+
+impl<'a, Position> Token<'a, Position> {
+	/// Converts all positions contained in this token (i.e. the inner spans of data literals) using `f`.
+	pub fn map_position<P>(self, f: impl FnMut(Position) -> P) -> Token<'a, P> {
+		match self {
+			Token::Comment(str) => Token::Comment(str),
+			Token::HeadingHashes(count) => Token::HeadingHashes(count),
+			Token::Newline => Token::Newline,
+			Token::Brac => Token::Brac,
+			Token::Ket => Token::Ket,
+			Token::Bra => Token::Bra,
+			Token::Ce => Token::Ce,
+			Token::Paren => Token::Paren,
+			Token::Thesis => Token::Thesis,
+			Token::Comma => Token::Comma,
+			Token::Period => Token::Period,
+			Token::String(str) => Token::String(str),
+			Token::InvalidStringWithVerbatimCarriageReturn(str) => {
+				Token::InvalidStringWithVerbatimCarriageReturn(str)
+			}
+			Token::DataLiteral(data_literal) => Token::DataLiteral(data_literal.map_position(f)),
+			Token::InvalidDataLiteralWithVerbatimCarriageReturn(invalid_data_literal) => {
+				Token::InvalidDataLiteralWithVerbatimCarriageReturn(
+					invalid_data_literal.map_position(f),
+				)
+			}
+			Token::Decimal(str) => Token::Decimal(str),
+			Token::InvalidZeroPrefixedDecimal(str) => Token::InvalidZeroPrefixedDecimal(str),
+			Token::Integer(str) => Token::Integer(str),
+			Token::InvalidZeroPrefixedInteger(str) => Token::InvalidZeroPrefixedInteger(str),
+			Token::Colon => Token::Colon,
+			Token::Identifier(str) => Token::Identifier(str),
+			Token::InvalidIdentifierWithVerbatimCarriageReturn(str) => {
+				Token::InvalidIdentifierWithVerbatimCarriageReturn(str)
+			}
+			Token::Error => Token::Error,
+		}
+	}
+}
+
 /// # Panics
 ///
 /// This [`Display`] implementation panics when called on [`Token::Error`].
@@ -353,3 +425,40 @@ fn lex() {
 		][..]
 	);
 }
+
+#[cfg(test)]
+#[test]
+fn lex_with_origin() {
+	use crate::FilePosition;
+
+	let source = "a: <`b c`:d\\\\>\n";
+	let tokens: Vec<_> = Token::lex(source, 10).collect();
+	assert_eq!(tokens[0], (Token::Identifier(Cow::Borrowed("a")), 10..11));
+	assert_eq!(
+		tokens[2],
+		(
+			Token::DataLiteral(DataLiteral {
+				encoding: Cow::Owned("b c".into()),
+				encoding_span: 14..19,
+				unencoded_data: Cow::Owned("d\\".into()),
+				unencoded_data_span: 20..23,
+			}),
+			13..24
+		)
+	);
+	assert_eq!(tokens[3].1, 24..25);
+
+	let origin = FilePosition::default();
+	assert!(Token::lex(source, origin)
+		.map(|(_, span)| span)
+		.eq(Token::lexer(source).spanned().map(|(_, span)| {
+			FilePosition {
+				offset: span.start,
+				..origin
+			}..FilePosition {
+				offset: span.end,
+				..origin
+			}
+		})));
+	assert!(Token::lex(source, ()).all(|(_, span)| span == (()..())));
+}
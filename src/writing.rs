@@ -0,0 +1,617 @@
+//! Writes parsed TAML structures back out as TAML text.
+//!
+//! The output is canonical for any given [`WriteOptions`]:
+//! Values are written inline wherever that's possible, maps and structured variants become (nested) sections,
+//! and lists containing structures are split into `# [list]` and `# [[list]]` sections as necessary.
+//!
+//! By default, chains of single-field maps are merged into path headings (`# a.b.c`)
+//! and lists of uniformly structured maps are written as tabular sections (`# [[rows].{a, b, c.{d}}]`).
+//!
+//! Everything written here round-trips through [`parse`](`crate::parse`),
+//! though spans are of course not preserved.
+
+use crate::{
+	parsing::{Key, Map, Taml, TamlValue, VariantPayload},
+	token::{escape_identifier, escape_string, escape_unencoded_data},
+	DataLiteral, Position,
+};
+use std::{
+	fmt::{self, Write},
+	ops::Range,
+};
+
+/// Reasons why a value could not be written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error<Position> {
+	/// The output [`Write`] implementation failed.
+	Fmt(fmt::Error),
+	/// A map or structured variant appears where only inline values can be written,
+	/// for example inside an inline list or as part of a tuple variant.
+	InlineStructure(Range<Position>),
+	/// A [`TamlValue::Error`] placeholder from error-recovering parsing, which has no textual representation.
+	ErrorValue(Range<Position>),
+}
+
+impl<Position> From<fmt::Error> for Error<Position> {
+	fn from(error: fmt::Error) -> Self {
+		Self::Fmt(error)
+	}
+}
+
+/// Layout options for [`write_with`] and [`to_string_with`].
+///
+/// The [`Default`] enables all layout heuristics and doesn't limit heading depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteOptions {
+	/// The preferred maximum number of hashes in headings.
+	///
+	/// Sections nested deeper than this are written as path headings (`## a.b.c`) at this depth instead,
+	/// as long as the path from there consists only of plain map keys.
+	/// Sections inside list items or structured variants exceed this limit where necessary.
+	pub max_heading_depth: Option<usize>,
+	/// Whether chains of single-field maps are merged into path headings like `# a.b.c`.
+	pub path_headings: bool,
+	/// Whether lists of two or more uniformly structured maps are written as tabular section,
+	/// like `# [[rows].{a, b, c.{d}}]`.
+	pub tables: bool,
+	/// How lists that contain only inline values are written.
+	pub lists: ListStyle,
+}
+
+impl Default for WriteOptions {
+	fn default() -> Self {
+		Self {
+			max_heading_depth: None,
+			path_headings: true,
+			tables: true,
+			lists: ListStyle::Inline,
+		}
+	}
+}
+
+/// How lists of only inline values are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListStyle {
+	/// As inline list, like `key: (1, 2, 3)`.
+	Inline,
+	/// As single-column tabular section (`# [[key]]`) with one value per line.
+	///
+	/// Empty lists are still written inline.
+	Sectioned,
+}
+
+/// Writes `map` as TAML document into `output`, using the default [`WriteOptions`].
+///
+/// # Errors
+///
+/// Iff `output` fails or `map` contains structures that can't be represented in TAML.
+pub fn write<P: Position>(output: &mut impl Write, map: &Map<'_, P>) -> Result<(), Error<P>> {
+	write_with(output, map, &WriteOptions::default())
+}
+
+/// Writes `map` as TAML document into `output`.
+///
+/// # Errors
+///
+/// Iff `output` fails or `map` contains structures that can't be represented in TAML.
+pub fn write_with<P: Position>(
+	output: &mut impl Write,
+	map: &Map<'_, P>,
+	options: &WriteOptions,
+) -> Result<(), Error<P>> {
+	Writer {
+		output,
+		options,
+		at_start: true,
+	}
+	.write_section(map, 0, None)
+}
+
+/// Writes `map` as TAML document into a new [`String`](`std::string::String`),
+/// using the default [`WriteOptions`].
+///
+/// # Errors
+///
+/// Iff `map` contains structures that can't be represented in TAML.
+pub fn to_string<P: Position>(map: &Map<'_, P>) -> Result<std::string::String, Error<P>> {
+	to_string_with(map, &WriteOptions::default())
+}
+
+/// Writes `map` as TAML document into a new [`String`](`std::string::String`).
+///
+/// # Errors
+///
+/// Iff `map` contains structures that can't be represented in TAML.
+pub fn to_string_with<P: Position>(
+	map: &Map<'_, P>,
+	options: &WriteOptions,
+) -> Result<std::string::String, Error<P>> {
+	let mut output = std::string::String::new();
+	write_with(&mut output, map, options)?;
+	Ok(output)
+}
+
+/// Writes `taml` as inline value (i.e. as it would appear on the right side of a key-value pair).
+///
+/// # Errors
+///
+/// Iff `output` fails or `taml` is or contains a map, structured variant or [`TamlValue::Error`].
+pub fn write_inline<P: Position>(
+	output: &mut impl Write,
+	taml: &Taml<'_, P>,
+) -> Result<(), Error<P>> {
+	match &taml.value {
+		TamlValue::String(string) => write!(output, "{}", escape_string(string))?,
+		TamlValue::DataLiteral(DataLiteral {
+			encoding,
+			unencoded_data,
+			..
+		}) => write!(
+			output,
+			"<{}:{}>",
+			escape_identifier(encoding),
+			escape_unencoded_data(unencoded_data)
+		)?,
+		TamlValue::Integer(str) | TamlValue::Decimal(str) => output.write_str(str)?,
+		TamlValue::List(list) => write_inline_list(output, list)?,
+		TamlValue::EnumVariant {
+			key,
+			payload: VariantPayload::Unit,
+		} => write!(output, "{}", escape_identifier(key))?,
+		TamlValue::EnumVariant {
+			key,
+			payload: VariantPayload::Tuple(list),
+		} => {
+			write!(output, "{}", escape_identifier(key))?;
+			write_inline_list(output, list)?
+		}
+		TamlValue::Map(_)
+		| TamlValue::EnumVariant {
+			payload: VariantPayload::Structured(_),
+			..
+		} => return Err(Error::InlineStructure(taml.span.clone())),
+		TamlValue::Error => return Err(Error::ErrorValue(taml.span.clone())),
+	}
+	Ok(())
+}
+
+fn write_inline_list<P: Position>(
+	output: &mut impl Write,
+	list: &[Taml<'_, P>],
+) -> Result<(), Error<P>> {
+	output.write_char('(')?;
+	for (i, item) in list.iter().enumerate() {
+		if i > 0 {
+			output.write_str(", ")?
+		}
+		write_inline(output, item)?
+	}
+	output.write_char(')')?;
+	Ok(())
+}
+
+/// Whether `taml` can be written on a single line.
+pub(crate) fn is_inline<P>(taml: &Taml<'_, P>) -> bool {
+	match &taml.value {
+		TamlValue::String(_)
+		| TamlValue::DataLiteral(_)
+		| TamlValue::Integer(_)
+		| TamlValue::Decimal(_)
+		| TamlValue::Error
+		| TamlValue::EnumVariant {
+			payload: VariantPayload::Unit,
+			..
+		} => true,
+		TamlValue::List(list)
+		| TamlValue::EnumVariant {
+			payload: VariantPayload::Tuple(list),
+			..
+		} => list.iter().all(is_inline),
+		TamlValue::Map(_)
+		| TamlValue::EnumVariant {
+			payload: VariantPayload::Structured(_),
+			..
+		} => false,
+	}
+}
+
+/// The key structure of a uniform table row, analogous to a tabular path segment.
+#[derive(PartialEq)]
+enum Column<'a> {
+	Value(&'a str),
+	Nested(&'a str, Vec<Column<'a>>),
+}
+
+impl Column<'_> {
+	fn arity(columns: &[Self]) -> usize {
+		columns
+			.iter()
+			.map(|column| match column {
+				Column::Value(_) => 1,
+				Column::Nested(_, nested) => Self::arity(nested),
+			})
+			.sum()
+	}
+
+	fn write_all(output: &mut impl Write, columns: &[Self]) -> fmt::Result {
+		output.write_char('{')?;
+		for (i, column) in columns.iter().enumerate() {
+			if i > 0 {
+				output.write_str(", ")?
+			}
+			match column {
+				Column::Value(key) => output.write_str(&escape_identifier(key))?,
+				Column::Nested(key, nested) => {
+					write!(output, "{}.", escape_identifier(key))?;
+					Self::write_all(output, nested)?
+				}
+			}
+		}
+		output.write_char('}')
+	}
+}
+
+/// Determines the table columns `map` could be written as, if any.
+fn columns<'a, P>(map: &'a Map<'_, P>) -> Option<Vec<Column<'a>>> {
+	map.iter()
+		.map(|(key, value)| match &value.value {
+			TamlValue::Map(nested) => Some(Column::Nested(key, columns(nested)?)),
+			_ if is_inline(value) => Some(Column::Value(key)),
+			_ => None,
+		})
+		.collect()
+}
+
+/// Writes the cells of `map` in column order, separated by commas.
+fn write_cells<P: Position>(
+	output: &mut impl Write,
+	map: &Map<'_, P>,
+	first: &mut bool,
+) -> Result<(), Error<P>> {
+	for value in map.values() {
+		if let TamlValue::Map(nested) = &value.value {
+			write_cells(output, nested, first)?
+		} else {
+			if !*first {
+				output.write_str(", ")?
+			}
+			*first = false;
+			write_inline(output, value)?
+		}
+	}
+	Ok(())
+}
+
+struct Writer<'a, W: Write> {
+	output: &'a mut W,
+	options: &'a WriteOptions,
+	at_start: bool,
+}
+
+impl<W: Write> Writer<'_, W> {
+	fn is_inline<P>(&self, taml: &Taml<'_, P>) -> bool {
+		match &taml.value {
+			TamlValue::List(list) if !list.is_empty() => {
+				self.options.lists == ListStyle::Inline && list.iter().all(is_inline)
+			}
+			_ => is_inline(taml),
+		}
+	}
+
+	/// Writes the contents of a section whose heading has `depth` hashes (`0` for the top level).
+	///
+	/// `path` is the heading's path iff it can be repeated to select the same section again.
+	fn write_section<P: Position>(
+		&mut self,
+		map: &Map<'_, P>,
+		depth: usize,
+		path: Option<&str>,
+	) -> Result<(), Error<P>> {
+		// Subsections at the maximum depth are written as siblings,
+		// so there is no way to return to this section afterwards.
+		let at_limit = self.options.max_heading_depth == Some(depth) && depth > 0;
+		let (inline, structured): (Vec<_>, Vec<_>) = if at_limit {
+			map.iter().partition(|(_, value)| self.is_inline(value))
+		} else {
+			(vec![], map.iter().collect())
+		};
+
+		let mut in_subsection = false;
+		for (key, value) in inline.into_iter().chain(structured) {
+			if self.is_inline(value) {
+				if in_subsection {
+					// An empty heading returns to the surrounding section.
+					self.heading(depth + 1)?;
+					self.end_line()?;
+					in_subsection = false;
+				}
+				write!(self.output, "{}: ", escape_identifier(key))?;
+				write_inline(self.output, value)?;
+				self.end_line()?;
+				continue;
+			}
+
+			// Subsections of a section at the maximum depth are flattened into path headings if possible.
+			// There's no way to return to this section after those, which is why its inline values come first.
+			let (child_depth, prefix) = match path {
+				Some(path) if at_limit => (depth, format!("{}.", path)),
+				_ => (depth + 1, std::string::String::new()),
+			};
+			in_subsection = child_depth > depth;
+
+			if let TamlValue::List(list) = &value.value {
+				self.write_list_sections(&prefix, key, list, child_depth)?;
+				continue;
+			}
+
+			let (element, map, reselectable) = self.path_element(key, value)?;
+			let child_path = format!("{}{}", prefix, element);
+			let flattened = reselectable
+				&& self.options.max_heading_depth == Some(child_depth)
+				&& !map.is_empty();
+			if !(flattened && map.values().all(|value| !self.is_inline(value))) {
+				// Otherwise, the heading would be empty and is implied by the following ones.
+				self.heading(child_depth)?;
+				write!(self.output, " {}", child_path)?;
+				self.end_line()?;
+			}
+			self.write_section(map, child_depth, reselectable.then(|| child_path.as_str()))?
+		}
+		Ok(())
+	}
+
+	/// Formats the heading path element(s) for `key` and `value`,
+	/// merging chains of single-field maps if enabled.
+	///
+	/// Returns the path, the map to write below it and whether selecting the path again is valid.
+	fn path_element<'b, 'c, P: Position>(
+		&self,
+		key: &Key<'_, P>,
+		value: &'b Taml<'c, P>,
+	) -> Result<(std::string::String, &'b Map<'c, P>, bool), Error<P>> {
+		let mut element = escape_identifier(key).to_string();
+		let (mut map, mut reselectable) = match &value.value {
+			TamlValue::Map(map) => (map, true),
+			TamlValue::EnumVariant {
+				key: variant,
+				payload: VariantPayload::Structured(map),
+			} => {
+				write!(element, ": {}", escape_identifier(variant))?;
+				(map, false)
+			}
+			_ => return Err(Error::InlineStructure(value.span.clone())),
+		};
+
+		while self.options.path_headings && map.len() == 1 {
+			let (key, value) = map.first().expect("unreachable");
+			match &value.value {
+				TamlValue::Map(nested) => {
+					write!(element, ".{}", escape_identifier(key))?;
+					map = nested;
+				}
+				TamlValue::EnumVariant {
+					key: variant,
+					payload: VariantPayload::Structured(nested),
+				} => {
+					write!(
+						element,
+						".{}: {}",
+						escape_identifier(key),
+						escape_identifier(variant)
+					)?;
+					map = nested;
+					reselectable = false;
+				}
+				_ => break,
+			}
+		}
+
+		Ok((element, map, reselectable))
+	}
+
+	/// Writes a list containing structures as sequence of `# [key]` and `# [[key]]` sections,
+	/// or as single tabular section if possible.
+	///
+	/// `prefix` is prepended to each heading's path.
+	fn write_list_sections<P: Position>(
+		&mut self,
+		prefix: &str,
+		key: &Key<'_, P>,
+		list: &[Taml<'_, P>],
+		depth: usize,
+	) -> Result<(), Error<P>> {
+		if self.options.tables && list.len() >= 2 {
+			if let Some(columns) = Self::table_columns(list) {
+				self.heading(depth)?;
+				write!(self.output, " {}[[{}].", prefix, escape_identifier(key))?;
+				Column::write_all(self.output, &columns)?;
+				self.output.write_char(']')?;
+				self.end_line()?;
+				for item in list {
+					if let TamlValue::Map(map) = &item.value {
+						write_cells(self.output, map, &mut true)?;
+						self.end_line()?;
+					}
+				}
+				return Ok(());
+			}
+		}
+
+		let mut in_table = false;
+		for item in list {
+			match &item.value {
+				_ if is_inline(item) => {
+					if !in_table {
+						self.heading(depth)?;
+						write!(self.output, " {}[[{}]]", prefix, escape_identifier(key))?;
+						self.end_line()?;
+						in_table = true;
+					}
+					write_inline(self.output, item)?;
+					self.end_line()?;
+				}
+				TamlValue::Map(map) => {
+					self.heading(depth)?;
+					write!(self.output, " {}[{}]", prefix, escape_identifier(key))?;
+					self.end_line()?;
+					self.write_section(map, depth, None)?;
+					in_table = false;
+				}
+				TamlValue::EnumVariant {
+					key: variant,
+					payload: VariantPayload::Structured(map),
+				} => {
+					self.heading(depth)?;
+					write!(
+						self.output,
+						" {}[{}]: {}",
+						prefix,
+						escape_identifier(key),
+						escape_identifier(variant)
+					)?;
+					self.end_line()?;
+					self.write_section(map, depth, None)?;
+					in_table = false;
+				}
+				_ => return Err(Error::InlineStructure(item.span.clone())),
+			}
+		}
+		Ok(())
+	}
+
+	/// Returns the shared columns of `list` iff all items are maps with the same non-empty structure.
+	fn table_columns<'b, P>(list: &'b [Taml<'_, P>]) -> Option<Vec<Column<'b>>> {
+		let mut items = list.iter().map(|item| match &item.value {
+			TamlValue::Map(map) => columns(map),
+			_ => None,
+		});
+		let first = items.next()??;
+		(Column::arity(&first) > 0 && items.all(|columns| columns.as_ref() == Some(&first)))
+			.then(|| first)
+	}
+
+	/// Writes heading hashes, preceded by an empty line unless at the start of the document.
+	fn heading(&mut self, depth: usize) -> fmt::Result {
+		if !self.at_start {
+			self.output.write_char('\n')?;
+		}
+		self.at_start = false;
+		self.output.write_str(&"#".repeat(depth))
+	}
+
+	fn end_line(&mut self) -> fmt::Result {
+		self.at_start = false;
+		self.output.write_char('\n')
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn round_trip() {
+	use crate::{parse, Token};
+	use logos::Logos as _;
+
+	let source = r#"top: "level"
+`quoted key`: <Some-Encoding:with \> escape>
+tuple: Tuple(1, 2.0, ("nested", Unit))
+
+# soundscapes
+name: "Sewer"
+loops: (("$sewer/amb_drips", 0.8), ("$sewer/amb_flies", 0.1))
+
+## [[mixed]]
+1
+2
+
+## [mixed]
+three: 3
+
+## [[mixed]]
+4
+
+## [moments]
+sound: "$sewer/moments/*"
+layers: 1
+
+## [moments]: Special
+volume-range: (0.1, 0.15)
+
+##
+after: "returned"
+
+# empty
+
+#
+last: ()
+"#;
+
+	let map = parse(Token::lexer(source).spanned(), &mut ()).unwrap();
+	let written = to_string(&map).unwrap();
+	assert_eq!(written, source);
+
+	let reparsed = parse(Token::lexer(&written).spanned(), &mut ()).unwrap();
+	assert_eq!(to_string(&reparsed).unwrap(), written);
+}
+
+#[cfg(test)]
+#[test]
+fn layout() {
+	use crate::{parse, Token};
+	use logos::Logos as _;
+
+	let source = r#"# a.b.c
+d: 1
+
+## [[rows].{id, name, price.{currency, amount}}]
+1, "A", EUR, 10.95
+2, "B", EUR, 5.5
+
+## deep
+e: 2
+
+### deeper
+f: 3
+"#;
+
+	let map = parse(Token::lexer(source).spanned(), &mut ()).unwrap();
+	assert_eq!(to_string(&map).unwrap(), source);
+
+	let options = WriteOptions {
+		max_heading_depth: Some(1),
+		path_headings: false,
+		tables: false,
+		lists: ListStyle::Sectioned,
+	};
+	let written = to_string_with(&map, &options).unwrap();
+	assert_eq!(
+		written,
+		r#"# a.b.c
+d: 1
+
+# a.b.c.[rows]
+id: 1
+name: "A"
+
+## price
+currency: EUR
+amount: 10.95
+
+# a.b.c.[rows]
+id: 2
+name: "B"
+
+## price
+currency: EUR
+amount: 5.5
+
+# a.b.c.deep
+e: 2
+
+# a.b.c.deep.deeper
+f: 3
+"#
+	);
+
+	let reparsed = parse(Token::lexer(&written).spanned(), &mut ()).unwrap();
+	assert_eq!(to_string(&reparsed).unwrap(), source);
+}
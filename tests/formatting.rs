@@ -0,0 +1,444 @@
+use logos::Logos as _;
+use proptest::prelude::*;
+use std::ops::Range;
+use taml::{
+	formatting::{
+		apply_edits, format, format_range, format_with, normalize_literals, Edit, FieldOrder,
+		FinalNewline, FormatOptions, LineEnding, TableStyle,
+	},
+	parse, writing, Token,
+};
+
+fn whitespace() -> impl Strategy<Value = String> {
+	"[ \t]{0,2}"
+}
+
+/// A key that is unique within the document, plain or quoted.
+fn key(letter: char, quoted: bool, id: usize) -> String {
+	if quoted {
+		format!("`{} {}`", letter, id)
+	} else {
+		format!("{}{}", letter, id)
+	}
+}
+
+fn value() -> impl Strategy<Value = String> {
+	let leaf = prop_oneof![
+		"-?(0|[1-9][0-9]{0,2})",
+		"-?[0-9]\\.[0-9]{1,3}",
+		r#""([a-z ]|\\\\|\\"){0,4}""#,
+		"[a-d]",
+		"<[a-d]:[a-z]{0,3}>",
+	];
+	leaf.prop_recursive(2, 8, 3, |inner| {
+		(prop::collection::vec(inner, 0..3), whitespace())
+			.prop_map(|(items, ws)| format!("({})", items.join(&format!(",{}", ws))))
+	})
+}
+
+fn comment() -> impl Strategy<Value = String> {
+	prop_oneof![Just(String::new()), "//[a-z ]{0,4}"]
+}
+
+#[derive(Debug, Clone)]
+enum Line {
+	/// A key-value pair, or a row in tabular sections.
+	Value {
+		whitespace: [String; 4],
+		key: (char, bool),
+		value: String,
+		other: String,
+		comment: String,
+	},
+	/// A heading at most `up` levels above the deepest allowed one.
+	Heading {
+		whitespace: [String; 2],
+		up: usize,
+		kind: HeadingKind,
+		path: Vec<(char, bool)>,
+		comment: String,
+	},
+	Comment(String, String),
+	Blank,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum HeadingKind {
+	Map,
+	List,
+	Table,
+}
+
+fn line() -> impl Strategy<Value = Line> {
+	let key = || {
+		("[a-d]", any::<bool>())
+			.prop_map(|(letter, quoted)| (letter.chars().next().unwrap(), quoted))
+	};
+	prop_oneof![
+		3 => (
+			[whitespace(), whitespace(), whitespace(), whitespace()],
+			key(),
+			value(),
+			value(),
+			comment(),
+		)
+			.prop_map(|(whitespace, key, value, other, comment)| Line::Value { whitespace, key, value, other, comment }),
+		1 => (
+			[whitespace(), whitespace()],
+			0..3_usize,
+			prop_oneof![Just(HeadingKind::Map), Just(HeadingKind::List), Just(HeadingKind::Table)],
+			prop::collection::vec(key(), 1..3),
+			comment(),
+		)
+			.prop_map(|(whitespace, up, kind, path, comment)| Line::Heading { whitespace, up, kind, path, comment }),
+		1 => (whitespace(), comment()).prop_map(|(ws, comment)| Line::Comment(ws, comment)),
+		1 => Just(Line::Blank),
+	]
+}
+
+/// Valid TAML documents, with keys and headings that never collide.
+fn document() -> impl Strategy<Value = String> {
+	prop::collection::vec((line(), prop_oneof!["\n", "\r\n"]), 0..12).prop_map(|lines| {
+		let mut ids = 0..;
+		let mut depth = 0_usize;
+		let mut tabular = false;
+		let mut document = String::new();
+		for (line, newline) in lines {
+			match line {
+				Line::Value {
+					whitespace: [a, b, c, d],
+					key: (letter, quoted),
+					value,
+					other,
+					comment,
+				} => {
+					if tabular {
+						document += &format!("{}{}{},{}{}{}{}", a, value, b, c, other, d, comment);
+					} else {
+						let key = key(letter, quoted, ids.next().unwrap());
+						document += &format!("{}{}{}:{}{}{}{}", a, key, b, c, value, d, comment);
+					}
+				}
+				Line::Heading {
+					whitespace: [a, b],
+					up,
+					kind,
+					path,
+					comment,
+				} => {
+					let deepest = if tabular { depth } else { depth + 1 };
+					depth = deepest.saturating_sub(up).max(1);
+					let mut path = path
+						.into_iter()
+						.map(|(letter, quoted)| key(letter, quoted, ids.next().unwrap()))
+						.collect::<Vec<_>>();
+					let last = path.pop().unwrap();
+					path.push(match kind {
+						HeadingKind::Map => last,
+						HeadingKind::List => format!("[{}]", last),
+						HeadingKind::Table => format!("[[{}].{{x, y}}]", last),
+					});
+					tabular = matches!(kind, HeadingKind::Table);
+					document += &format!(
+						"{}{}{}{} {}",
+						a,
+						"#".repeat(depth),
+						b,
+						path.join("."),
+						comment
+					);
+				}
+				Line::Comment(ws, comment) => document += &format!("{}{}", ws, comment),
+				Line::Blank => (),
+			}
+			document += newline.as_str();
+		}
+		document
+	})
+}
+
+fn options() -> impl Strategy<Value = FormatOptions> {
+	(
+		0..3_usize,
+		prop::option::of(0..3_usize),
+		0..3_usize,
+		any::<bool>(),
+		prop_oneof![
+			Just(FinalNewline::Preserve),
+			Just(FinalNewline::Ensure),
+			Just(FinalNewline::Remove)
+		],
+		prop_oneof![Just(LineEnding::Lf), Just(LineEnding::CrLf)],
+		any::<bool>(),
+		prop_oneof![Just(TableStyle::Compact), Just(TableStyle::Aligned)],
+	)
+		.prop_map(
+			|(
+				blank_lines_before_headings,
+				blank_lines_after_headings,
+				max_blank_lines,
+				normalize_comment_space,
+				final_newline,
+				line_ending,
+				sort,
+				table_style,
+			)| FormatOptions {
+				blank_lines_before_headings,
+				blank_lines_after_headings,
+				max_blank_lines,
+				normalize_comment_space,
+				final_newline,
+				line_ending,
+				sort_fields: sort.then(|| FieldOrder::Alphabetical),
+				table_style,
+			},
+		)
+}
+
+fn line_options() -> impl Strategy<Value = FormatOptions> {
+	options().prop_map(|options| FormatOptions {
+		sort_fields: None,
+		table_style: TableStyle::Compact,
+		..options
+	})
+}
+
+/// The part of `source` that [`format_range`] may change for `range`:
+/// the lines `range` touches, blank lines directly before them and, if only blank lines follow, the end of the document.
+fn touched(source: &str, range: &Range<usize>) -> Range<usize> {
+	let line_start = source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+	let before = source[..line_start].trim_end();
+	let start = if before.is_empty() {
+		0
+	} else {
+		before.len() + source[before.len()..].find('\n').unwrap() + 1
+	};
+
+	let last = range
+		.end
+		.max(range.start + 1)
+		.min(source.len())
+		.saturating_sub(1);
+	let end = source[last..]
+		.find('\n')
+		.map_or(source.len(), |i| last + i + 1);
+	let end = if source[end..].trim().is_empty() {
+		source.len()
+	} else {
+		end
+	};
+	start..end
+}
+
+fn canonical(source: &str) -> String {
+	let map = parse(Token::lexer(source).spanned(), &mut ()).unwrap();
+	writing::to_string(&map).unwrap()
+}
+
+proptest! {
+	#![proptest_config(ProptestConfig::with_cases(2048))]
+
+	#[test]
+	fn idempotent(source in document()) {
+		let formatted = format(&source);
+		prop_assert!(formatted.is_ok(), "{:?}", formatted);
+		let formatted = formatted.unwrap();
+		prop_assert_eq!(format(&formatted), Ok(formatted.clone()));
+		prop_assert_eq!(canonical(&formatted), canonical(&source));
+	}
+
+	#[test]
+	fn idempotent_with_options(source in document(), options in options()) {
+		let formatted = format_with(&source, &options).unwrap();
+		prop_assert_eq!(format_with(&formatted, &options), Ok(formatted.clone()));
+	}
+
+	#[test]
+	fn whole_range(source in document(), options in line_options()) {
+		let formatted = format_with(&source, &options).unwrap();
+		let edits = format_range(&source, 0..source.len(), &options).unwrap();
+		prop_assert_eq!(apply_edits(&source, &edits), formatted);
+	}
+
+	#[test]
+	fn partial_range(source in document(), options in line_options(), a in 0..1000_usize, b in 0..1000_usize) {
+		let formatted = format_with(&source, &options).unwrap();
+		let (a, b) = (a.min(source.len()), b.min(source.len()));
+		let range = a.min(b)..a.max(b);
+		if source.is_char_boundary(range.start) && source.is_char_boundary(range.end) {
+			let touched = touched(&source, &range);
+			let edits = format_range(&source, range, &options).unwrap();
+			let partially_formatted = apply_edits(&source, &edits);
+			prop_assert!(partially_formatted.starts_with(&source[..touched.start]));
+			prop_assert!(partially_formatted[touched.start..].ends_with(&source[touched.end..]));
+			prop_assert_eq!(format_with(&partially_formatted, &options), Ok(formatted));
+		}
+	}
+}
+
+#[test]
+fn invalid() {
+	for source in ["true:-0###:(:0.50", "a: 1\na: 2\n", "# [[a].{b, c}]\n1\n"] {
+		assert!(format(source).is_err(), "{:?}", source);
+		assert!(
+			format_with(source, &FormatOptions::default()).is_err(),
+			"{:?}",
+			source
+		);
+		assert!(
+			format_range(source, 0..source.len(), &FormatOptions::default()).is_err(),
+			"{:?}",
+			source
+		);
+	}
+}
+
+#[test]
+fn layout() {
+	assert_eq!(
+		format(
+			"\n\n// Comment\na:1 //postfix  \n\n\n\n# [[b].{c, d}]\n\"x\",2.50\n  \"y\" ,  3\n# \n"
+		)
+		.unwrap(),
+		"// Comment\na: 1 //postfix\n\n# [[b].{c, d}]\n\"x\", 2.5\n\"y\", 3\n\n#\n"
+	);
+}
+
+#[test]
+fn sort_fields() {
+	let source = "c: 1\nb: 2 // b\na: 3\n\nz: 4\n// y\ny: 5\nx: 6\n\n# section\nb: 1\na: 2";
+	assert_eq!(
+		format_with(
+			source,
+			&FormatOptions {
+				sort_fields: Some(FieldOrder::Alphabetical),
+				..FormatOptions::default()
+			}
+		)
+		.unwrap(),
+		"a: 3\nb: 2 // b\nc: 1\n\nz: 4\n// y\nx: 6\ny: 5\n\n# section\na: 2\nb: 1"
+	);
+
+	// No block is in reverse order yet, and only the last one is in alphabetical order.
+	let source = "b: 1\nc: 2 // c\na: 3\n\n// z\nx: 4\nz: 5\ny: 6\n\n# section\na: 1\nb: 2";
+	assert_eq!(
+		format_with(
+			source,
+			&FormatOptions {
+				sort_fields: Some(FieldOrder::Custom(|a, b| b.cmp(a))),
+				..FormatOptions::default()
+			}
+		)
+		.unwrap(),
+		"c: 2 // c\nb: 1\na: 3\n\n// z\nz: 5\ny: 6\nx: 4\n\n# section\nb: 2\na: 1"
+	);
+}
+
+#[test]
+fn align_tables() {
+	let source = "# [[dishes].{id, name, [price].{currency, amount}}]\n1, \"Soup\", <currency:EUR>, 4.5 // starter\n\n12,\"Lasagna\",<currency:EUR>,11\n";
+	let aligned = "# [[dishes].{id, name, [price].{currency, amount}}]\n1 , \"Soup\"   , <currency:EUR>, 4.5 // starter\n\n12, \"Lasagna\", <currency:EUR>, 11\n";
+	let options = FormatOptions {
+		table_style: TableStyle::Aligned,
+		..FormatOptions::default()
+	};
+	assert_eq!(format_with(source, &options).unwrap(), aligned);
+	assert_eq!(
+		format(aligned).unwrap(),
+		"# [[dishes].{id, name, [price].{currency, amount}}]\n1, \"Soup\", <currency:EUR>, 4.5 // starter\n\n12, \"Lasagna\", <currency:EUR>, 11\n"
+	);
+}
+
+#[test]
+fn blank_lines_and_line_endings() {
+	let source = "//a\n# a\nb: 1\n\n\n\nc: 2\n#\n\n\n//   d\ne: 3\n";
+	assert_eq!(
+		format_with(
+			source,
+			&FormatOptions {
+				blank_lines_before_headings: 2,
+				blank_lines_after_headings: Some(1),
+				max_blank_lines: 2,
+				normalize_comment_space: true,
+				final_newline: FinalNewline::Remove,
+				line_ending: LineEnding::CrLf,
+				..FormatOptions::default()
+			}
+		)
+		.unwrap(),
+		"// a\r\n\r\n\r\n# a\r\n\r\nb: 1\r\n\r\n\r\nc: 2\r\n\r\n\r\n#\r\n\r\n// d\r\ne: 3"
+	);
+	assert_eq!(
+		format_with(
+			"a: 1",
+			&FormatOptions {
+				final_newline: FinalNewline::Ensure,
+				..FormatOptions::default()
+			}
+		)
+		.unwrap(),
+		"a: 1\n"
+	);
+}
+
+#[test]
+fn literals() {
+	let source =
+		"# `plain_name`\n`a b`: \"\\\\ \\r\" // `kept`\nc: 0.1000\nd: <`hex`:0f>\ne: 1.0\n";
+	let edit = |original: &str, replacement: &str| {
+		let start = source.find(original).unwrap();
+		Edit {
+			span: start..start + original.len(),
+			replacement: replacement.to_string(),
+		}
+	};
+	let edits = normalize_literals(source);
+	assert_eq!(
+		edits,
+		[
+			edit("`plain_name`", "plain_name"),
+			edit("0.1000", "0.1"),
+			edit("<`hex`:0f>", "<hex:0f>"),
+		]
+	);
+	assert_eq!(
+		apply_edits(source, &edits),
+		"# plain_name\n`a b`: \"\\\\ \\r\" // `kept`\nc: 0.1\nd: <hex:0f>\ne: 1.0\n"
+	);
+}
+
+#[test]
+fn range() {
+	let source = "a:1\n\n\n\n# b\nc :  2 \nd:3\n\n\n";
+	let start = source.find("c").unwrap();
+	let edits = format_range(source, start..start + 1, &FormatOptions::default()).unwrap();
+	assert_eq!(
+		edits,
+		[
+			Edit {
+				span: start + 1..start + 2,
+				replacement: String::new(),
+			},
+			Edit {
+				span: start + 4..start + 5,
+				replacement: String::new(),
+			},
+			Edit {
+				span: start + 6..start + 7,
+				replacement: String::new(),
+			},
+		]
+	);
+	assert_eq!(
+		apply_edits(source, &edits),
+		"a:1\n\n\n\n# b\nc: 2\nd:3\n\n\n"
+	);
+
+	let edits = format_range(
+		source,
+		source.find("#").unwrap()..source.len(),
+		&FormatOptions::default(),
+	)
+	.unwrap();
+	assert_eq!(apply_edits(source, &edits), "a:1\n\n# b\nc: 2\nd: 3\n");
+}